@@ -0,0 +1,196 @@
+use crate::Point;
+use std::collections::HashMap;
+use std::fmt;
+
+const BACKGROUND: [u8; 3] = [10, 10, 30];
+const STAR: [u8; 3] = [255, 230, 140];
+
+// A frame as palette indices: 0 is sky, 1 a star
+pub struct Frame {
+    pub time: i64,
+    pub pixels: Vec<u8>,
+}
+
+// Frames every `stride` seconds from `window` before the given time to
+// `window` after it, never before the start. Each frame is cropped to its own
+// stars and scaled to fit the canvas, whose shape follows the converged
+// message so it fills the picture when the stars line up.
+pub fn frames(points: &[Point], center: i64, window: i64, stride: i64, size: usize) -> (usize, usize, Vec<Frame>) {
+    let bounds = |time: i64| {
+        let moved: Vec<Point> = points.iter().map(|p| p.at(time)).collect();
+        (
+            moved.iter().map(|p| p.x).min().unwrap(),
+            moved.iter().map(|p| p.y).min().unwrap(),
+            moved.iter().map(|p| p.x).max().unwrap(),
+            moved.iter().map(|p| p.y).max().unwrap(),
+        )
+    };
+
+    let (min_x, min_y, max_x, max_y) = bounds(center);
+    let (span_x, span_y) = ((max_x - min_x + 1) as f64, (max_y - min_y + 1) as f64);
+    let fit = size as f64 / span_x.max(span_y);
+    let width = ((span_x * fit).round() as usize).max(1);
+    let height = ((span_y * fit).round() as usize).max(1);
+
+    let mut frames: Vec<Frame> = Vec::new();
+    let mut time = (center - window).max(0);
+    while time <= center + window {
+        let (min_x, min_y, max_x, max_y) = bounds(time);
+        let scale = (width as f64 / (max_x - min_x + 1) as f64).min(height as f64 / (max_y - min_y + 1) as f64);
+        let offset_x = (width as f64 - (max_x - min_x + 1) as f64 * scale) / 2.0;
+        let offset_y = (height as f64 - (max_y - min_y + 1) as f64 * scale) / 2.0;
+
+        // Stars at least a pixel across, however far the view is zoomed out
+        let mut pixels = vec![0; width * height];
+        for p in points.iter().map(|p| p.at(time)) {
+            let x0 = offset_x + (p.x - min_x) as f64 * scale;
+            let y0 = offset_y + (p.y - min_y) as f64 * scale;
+            for y in (y0 as usize)..((y0 + scale).ceil().max(y0 + 1.0) as usize).min(height) {
+                for x in (x0 as usize)..((x0 + scale).ceil().max(x0 + 1.0) as usize).min(width) {
+                    pixels[y * width + x] = 1;
+                }
+            }
+        }
+
+        frames.push(Frame { time, pixels });
+        time += stride.max(1);
+    }
+
+    (width, height, frames)
+}
+
+pub fn ppm(width: usize, height: usize, frame: &Frame) -> Vec<u8> {
+    let mut out = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    for &pixel in &frame.pixels {
+        out.extend(if pixel == 1 { STAR } else { BACKGROUND });
+    }
+
+    out
+}
+
+// Packs variable width codes least significant bit first, as GIF wants
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u32) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+// GIF's LZW: codes start one bit wider than the palette indices and grow as
+// the table does, up to 12 bits, after which the table starts over
+pub fn lzw(indices: &[u8], min_code_size: u32) -> Vec<u8> {
+    let clear: u16 = 1 << min_code_size;
+    let end = clear + 1;
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = end + 1;
+    let mut code_size = min_code_size + 1;
+    let mut writer = BitWriter {
+        bytes: Vec::new(),
+        buffer: 0,
+        bits: 0,
+    };
+
+    writer.write(clear, code_size);
+    let mut prefix: Option<u16> = None;
+    for &index in indices {
+        let Some(current) = prefix else {
+            prefix = Some(index as u16);
+            continue;
+        };
+
+        if let Some(&code) = table.get(&(current, index)) {
+            prefix = Some(code);
+            continue;
+        }
+
+        writer.write(current, code_size);
+        if next_code < 4096 {
+            table.insert((current, index), next_code);
+            next_code += 1;
+            if next_code > 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+        } else {
+            writer.write(clear, code_size);
+            table.clear();
+            next_code = end + 1;
+            code_size = min_code_size + 1;
+        }
+        prefix = Some(index as u16);
+    }
+
+    if let Some(current) = prefix {
+        writer.write(current, code_size);
+    }
+    writer.write(end, code_size);
+    writer.finish()
+}
+
+// GIF stores the canvas size in 16 bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TooLarge {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}x{} is bigger than a GIF's {}x{} limit", self.width, self.height, u16::MAX, u16::MAX)
+    }
+}
+
+// An endlessly looping animated GIF, `delay` hundredths of a second a frame
+pub fn gif(width: usize, height: usize, frames: &[Frame], delay: u16) -> Result<Vec<u8>, TooLarge> {
+    let (Ok(gif_width), Ok(gif_height)) = (u16::try_from(width), u16::try_from(height)) else {
+        return Err(TooLarge { width, height });
+    };
+
+    let mut out: Vec<u8> = b"GIF89a".to_vec();
+    out.extend(gif_width.to_le_bytes());
+    out.extend(gif_height.to_le_bytes());
+    out.extend([0xf1, 0, 0]);
+    out.extend(BACKGROUND);
+    out.extend(STAR);
+    out.extend([0; 6]);
+    out.extend(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+    for frame in frames {
+        out.extend([0x21, 0xf9, 4, 0]);
+        out.extend(delay.to_le_bytes());
+        out.extend([0, 0]);
+
+        out.push(0x2c);
+        out.extend([0, 0, 0, 0]);
+        out.extend(gif_width.to_le_bytes());
+        out.extend(gif_height.to_le_bytes());
+        out.push(0);
+
+        out.push(2);
+        for block in lzw(&frame.pixels, 2).chunks(255) {
+            out.push(block.len() as u8);
+            out.extend(block);
+        }
+        out.push(0);
+    }
+
+    out.push(0x3b);
+    Ok(out)
+}
@@ -5,7 +5,16 @@ use std::env;
 use std::fs;
 use std::time::Instant;
 
-fn part1(contents: String) -> String {
+mod animate;
+mod ocr;
+
+fn part1(contents: String) -> Result<String, ocr::OcrError> {
+    let points: Vec<(i64, i64)> = determine_message(contents).0.iter().map(|p| (p.x, p.y)).collect();
+    ocr::read(&points)
+}
+
+// The message as a block of characters, for when it can't be read
+fn draw(contents: String) -> String {
     let points = determine_message(contents).0;
     let min_x = points.iter().map(|p| p.x).min().unwrap();
     let max_x = points.iter().map(|p| p.x).max().unwrap();
@@ -46,21 +55,48 @@ impl Point {
         Point { x, y, x_v, y_v }
     }
 
-    fn step(&mut self) {
-        self.x += self.x_v;
-        self.y += self.y_v;
+    fn at(&self, time: i64) -> Point {
+        Point::new(self.x + self.x_v * time, self.y + self.y_v * time, self.x_v, self.y_v)
     }
+}
+
+fn area(points: &[Point], time: i64) -> i64 {
+    let moved: Vec<Point> = points.iter().map(|p| p.at(time)).collect();
+    let min_x = moved.iter().map(|p| p.x).min().unwrap();
+    let max_x = moved.iter().map(|p| p.x).max().unwrap();
+    let min_y = moved.iter().map(|p| p.y).min().unwrap();
+    let max_y = moved.iter().map(|p| p.y).max().unwrap();
 
-    fn step_back(&mut self) {
-        self.x -= self.x_v;
-        self.y -= self.y_v;
+    (max_x - min_x) * (max_y - min_y)
+}
+
+// The spread of the points, summed over both axes, is a quadratic in time:
+// Var(p + vt) = Var(p) + 2t Cov(p, v) + t^2 Var(v). Its minimum lands on or
+// right next to the frame where the message shows.
+fn convergence_estimate(points: &[Point]) -> i64 {
+    let n = points.len() as f64;
+    let mean = |f: &dyn Fn(&Point) -> i64| points.iter().map(|p| f(p) as f64).sum::<f64>() / n;
+    let (mx, my) = (mean(&|p| p.x), mean(&|p| p.y));
+    let (mvx, mvy) = (mean(&|p| p.x_v), mean(&|p| p.y_v));
+
+    let mut cov = 0.0;
+    let mut var = 0.0;
+    for p in points {
+        let (dvx, dvy) = (p.x_v as f64 - mvx, p.y_v as f64 - mvy);
+        cov += (p.x as f64 - mx) * dvx + (p.y as f64 - my) * dvy;
+        var += dvx * dvx + dvy * dvy;
+    }
+
+    if var == 0.0 {
+        0
+    } else {
+        (-cov / var).round().max(0.0) as i64
     }
 }
 
-#[cached]
-fn determine_message(contents: String) -> (Vec<Point>, i64) {
+fn parse_points(contents: &str) -> Vec<Point> {
     let int_re: Regex = Regex::new(r"-?\d+").unwrap();
-    let mut points: Vec<Point> = contents
+    contents
         .lines()
         .map(|line| {
             let mut nums: Vec<i64> = int_re
@@ -74,40 +110,199 @@ fn determine_message(contents: String) -> (Vec<Point>, i64) {
                 nums.remove(0),
             )
         })
-        .collect();
-
-    let mut p_area = i64::MAX;
-    let mut area = (points.iter().map(|p| p.x).max().unwrap()
-        - points.iter().map(|p| p.x).min().unwrap())
-        * (points.iter().map(|p| p.y).max().unwrap() - points.iter().map(|p| p.y).min().unwrap());
-    let mut steps: i64 = 0;
-
-    while area < p_area {
-        p_area = area;
+        .collect()
+}
 
-        for point in points.iter_mut() {
-            point.step();
-        }
+#[cached]
+fn determine_message(contents: String) -> (Vec<Point>, i64) {
+    let points = parse_points(&contents);
 
-        let min_x = points.iter().map(|p| p.x).min().unwrap();
-        let max_x = points.iter().map(|p| p.x).max().unwrap();
-        let min_y = points.iter().map(|p| p.y).min().unwrap();
-        let max_y = points.iter().map(|p| p.y).max().unwrap();
-        area = (max_x - min_x) * (max_y - min_y);
-        steps += 1;
+    // Walk to the first frame whose area the next one doesn't beat, which is
+    // where stepping one second at a time from the start would stop
+    let mut time = convergence_estimate(&points);
+    while area(&points, time + 1) < area(&points, time) {
+        time += 1;
     }
-
-    for point in points.iter_mut() {
-        point.step_back();
+    while time > 0 && area(&points, time - 1) <= area(&points, time) {
+        time -= 1;
     }
 
-    return (points, steps - 1);
+    (points.iter().map(|p| p.at(time)).collect(), time)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    // Puts the stars of a # and . drawing where they'd be `seconds` earlier.
+    // Neighbouring stars drift opposite ways, so the drawing is the tightest
+    // the stars ever get.
+    fn rewind(drawing: &str, seconds: i64) -> String {
+        let mut lines: Vec<String> = Vec::new();
+        for (y, row) in drawing.lines().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == '#' {
+                    let (x_v, y_v) = (1 - 2 * (y as i64 % 2), 1 - 2 * (x as i64 % 2));
+                    let (x, y) = (x as i64 - x_v * seconds, y as i64 - y_v * seconds);
+                    lines.push(format!("position=<{}, {}> velocity=<{}, {}>", x, y, x_v, y_v));
+                }
+            }
+        }
+
+        lines.join("\n")
+    }
+
+    #[test]
+    fn ocr_test() {
+        let small = "\
+.##..###...##..####...##
+#..#.#..#.#..#.#.......#
+#..#.###..#....###.....#
+####.#..#.#....#.......#
+#..#.#..#.#..#.#....#..#
+#..#.###...##..####..##.";
+        let large = "\
+#####...######..#....#
+#....#.......#..#....#
+#....#.......#...#..#.
+#....#......#....#..#.
+#####......#......##..
+#..#......#.......##..
+#...#....#.......#..#.
+#...#...#........#..#.
+#....#..#.......#....#
+#....#..######..#....#";
+
+        assert_eq!(part1(rewind(small, 50)), Ok("ABCEJ".to_string()));
+        assert_eq!(part1(rewind(large, 10450)), Ok("RZX".to_string()));
+        assert_eq!(part2(rewind(large, 10450)), 10450);
+
+        let garbled = small.replace(".##..###", ".##.####");
+        assert_eq!(
+            part1(rewind(&garbled, 3)),
+            Err(ocr::OcrError::UnknownGlyph { index: 0, glyph: garbled.lines().map(|l| &l[..9]).collect::<Vec<&str>>().join("\n") })
+        );
+        assert_eq!(ocr::read(&[(0, 0), (3, 4)]), Err(ocr::OcrError::UnsupportedHeight(5)));
+    }
+
+    #[test]
+    fn convergence_test() {
+        let contents = "\
+position=< 9,  1> velocity=< 0,  2>
+position=< 7,  0> velocity=<-1,  0>
+position=< 3, -2> velocity=<-1,  1>
+position=< 6, 10> velocity=<-2, -1>
+position=< 2, -4> velocity=< 2,  2>
+position=<-6, 10> velocity=< 2, -2>
+position=< 1,  8> velocity=< 1, -1>
+position=< 1,  7> velocity=< 1,  0>
+position=<-3, 11> velocity=< 1, -2>
+position=< 7,  6> velocity=<-1, -1>
+position=<-2,  3> velocity=< 1,  0>
+position=<-4,  3> velocity=< 2,  0>
+position=<10, -3> velocity=<-1,  1>
+position=< 5, 11> velocity=< 1, -2>
+position=< 4,  7> velocity=< 0, -1>
+position=< 8, -2> velocity=< 0,  1>
+position=<15,  0> velocity=<-2,  0>
+position=< 1,  6> velocity=< 1,  0>
+position=< 8,  9> velocity=< 0, -1>
+position=< 3,  3> velocity=<-1,  1>
+position=< 0,  5> velocity=< 0, -1>
+position=<-2,  2> velocity=< 2,  0>
+position=< 5, -2> velocity=< 1,  2>
+position=< 1,  4> velocity=< 2,  1>
+position=<-2,  7> velocity=< 2, -2>
+position=< 3,  6> velocity=<-1, -1>
+position=< 5,  0> velocity=< 1,  0>
+position=<-6,  0> velocity=< 2,  0>
+position=< 5,  9> velocity=< 1, -2>
+position=<14,  7> velocity=<-2,  0>
+position=<-3,  6> velocity=< 2, -1>";
+        assert_eq!(part2(contents.to_string()), 3);
+        assert_eq!(draw(contents.to_string()).matches('█').count(), 31);
+
+        // Against stepping through every second from the start
+        for seconds in [0, 1, 7, 250, 3001] {
+            let input = rewind("#..#.\n####.\n#..##\n.#..#", seconds);
+            let points = parse_points(&input);
+
+            let mut time = 0;
+            while area(&points, time + 1) < area(&points, time) {
+                time += 1;
+            }
+            assert_eq!(part2(input), time);
+        }
+    }
+
+    // Just enough of a GIF decoder's LZW to check the encoder against
+    fn unlzw(data: &[u8], min_code_size: u32) -> Vec<u8> {
+        let clear = 1usize << min_code_size;
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut code_size = min_code_size + 1;
+        let mut previous: Option<usize> = None;
+        let mut output: Vec<u8> = Vec::new();
+        let (mut buffer, mut bits, mut bytes) = (0u32, 0u32, data.iter());
+
+        loop {
+            while bits < code_size {
+                buffer |= (*bytes.next().unwrap() as u32) << bits;
+                bits += 8;
+            }
+            let code = (buffer & ((1 << code_size) - 1)) as usize;
+            buffer >>= code_size;
+            bits -= code_size;
+
+            if code == clear {
+                table = (0..clear).map(|i| vec![i as u8]).chain([vec![], vec![]]).collect();
+                code_size = min_code_size + 1;
+                previous = None;
+                continue;
+            }
+            if code == clear + 1 {
+                return output;
+            }
+
+            let entry = match (table.get(code), previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(p)) => [table[p].clone(), vec![table[p][0]]].concat(),
+                (None, None) => panic!("Bad code {}", code),
+            };
+            if let Some(p) = previous {
+                table.push([table[p].clone(), vec![entry[0]]].concat());
+            }
+            output.extend(&entry);
+            previous = Some(code);
+            if table.len() == 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn animate_test() {
+        let noise: Vec<u8> = (0..40_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 29) as u8 % 4).collect();
+        let runs: Vec<u8> = (0..10_000).map(|i| (i / 37 % 2) as u8).collect();
+        for indices in [noise, runs, vec![1], vec![]] {
+            assert_eq!(unlzw(&animate::lzw(&indices, 2), 2), indices);
+        }
+
+        let input = rewind("#..#.\n####.\n#..##\n.#..#", 40);
+        let (width, height, frames) = animate::frames(&parse_points(&input), 40, 5, 2, 50);
+        assert_eq!((width, height), (50, 40));
+        assert_eq!(frames.iter().map(|f| f.time).collect::<Vec<i64>>(), vec![35, 37, 39, 41, 43, 45]);
+        assert!(frames.iter().all(|f| f.pixels.len() == 50 * 40 && f.pixels.contains(&1)));
+
+        let gif = animate::gif(width, height, &frames, 5).unwrap();
+        assert!(gif.starts_with(b"GIF89a\x32\x00\x28\x00"));
+        assert_eq!(gif.last(), Some(&0x3b));
+        assert_eq!(animate::ppm(width, height, &frames[0]).len(), "P6\n50 40\n255\n".len() + 50 * 40 * 3);
+        assert_eq!(
+            animate::gif(70_000, 40, &[], 5),
+            Err(animate::TooLarge { width: 70_000, height: 40 })
+        );
+    }
+
     #[test]
     fn p2_test() {
         let contents =
@@ -118,12 +313,14 @@ mod tests {
 }
 fn main() {
     let args: Vec<String> = env::args().collect();
+    let (flags, paths): (Vec<String>, Vec<String>) =
+        args.iter().skip(1).cloned().partition(|arg| arg.starts_with("--"));
     let year = "2018".to_string();
     let day = "10".to_string();
 
     let root = env::current_dir().unwrap();
-    let path_str = if args.len() > 1 {
-        args[1].clone()
+    let path_str = if !paths.is_empty() {
+        paths[0].clone()
     } else if root.ends_with(format!("{}", day)) {
         format!("../../../Inputs/{}_{}.txt", year, day)
     } else {
@@ -131,13 +328,13 @@ fn main() {
     };
 
 
-    let contents = fs::read_to_string(if args.len() > 1 {path_str} else {RelativePath::new(&path_str).to_path(&root).display().to_string()})
+    let contents = fs::read_to_string(if !paths.is_empty() {path_str} else {RelativePath::new(&path_str).to_path(&root).display().to_string()})
         .expect("Should have been able to read the file");
 
     let part1_timer = Instant::now();
     println!(
         "\nPart 1:\nMessage: {}\nRan in {:.5?}",
-        part1(contents.clone()),
+        part1(contents.clone()).unwrap_or_else(|error| format!("{}\n{}", error, draw(contents.clone()))),
         part1_timer.elapsed()
     );
 
@@ -147,4 +344,52 @@ fn main() {
         part2(contents.clone()),
         part2_timer.elapsed()
     );
-}
\ No newline at end of file
+
+    let setting = |name: &str| flags.iter().find_map(|flag| flag.strip_prefix(name));
+    let Some(window) = setting("--window=").map_or(Some(10), |n| n.parse::<i64>().ok().filter(|&w| w >= 0)) else {
+        println!("\nInvalid window:\nShould have been a number of seconds, 0 or more");
+        return;
+    };
+    let Some(stride) = setting("--stride=").map_or(Some(1), |n| n.parse::<i64>().ok().filter(|&s| s >= 1)) else {
+        println!("\nInvalid stride:\nShould have been a number of seconds, 1 or more");
+        return;
+    };
+    let Some(size) = setting("--size=").map_or(Some(320), |n| n.parse::<usize>().ok().filter(|&s| s >= 1)) else {
+        println!("\nInvalid size:\nShould have been a number of pixels, 1 or more");
+        return;
+    };
+    let Some(delay) = setting("--delay=").map_or(Some(10), |n| n.parse::<u16>().ok()) else {
+        println!("\nInvalid delay:\nShould have been a number of hundredths of a second, at most {}", u16::MAX);
+        return;
+    };
+
+    // Only worked out if there's something to write
+    let points = parse_points(&contents);
+    let animation = || animate::frames(&points, part2(contents.clone()), window, stride, size);
+
+    for flag in &flags {
+        match flag.split_once('=') {
+            Some(("--gif", path)) => {
+                let (width, height, frames) = animation();
+                match animate::gif(width, height, &frames, delay) {
+                    Ok(gif) => {
+                        fs::write(path, gif).expect("Should have been able to write the file");
+                        println!("\nWrote {} frames to {}", frames.len(), path);
+                    }
+                    Err(error) => println!("\nInvalid size:\n{}", error),
+                }
+            }
+            Some(("--ppm", prefix)) => {
+                let (width, height, frames) = animation();
+                // Numbered by second, so the file names say when each frame is
+                for frame in &frames {
+                    let path = format!("{}{:06}.ppm", prefix, frame.time);
+                    fs::write(&path, animate::ppm(width, height, frame)).expect("Should have been able to write the file");
+                }
+                println!("\nWrote {} frames to {}*.ppm", frames.len(), prefix);
+            }
+            Some(("--window", _)) | Some(("--stride", _)) | Some(("--size", _)) | Some(("--delay", _)) => {}
+            _ => panic!("Unknown option {}", flag),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::HashSet;
+use std::fmt;
+
+// The two fonts Advent of Code draws letters in, as # and . rows. Glyphs
+// are split apart on empty columns, so each pattern is only as wide as its
+// letter.
+const LARGE_HEIGHT: usize = 10;
+const LARGE_FONT: [(char, &str); 15] = [
+    ('A', "..##.. .#..#. #....# #....# #....# ###### #....# #....# #....# #....#"),
+    ('B', "#####. #....# #....# #....# #####. #....# #....# #....# #....# #####."),
+    ('C', ".####. #....# #..... #..... #..... #..... #..... #..... #....# .####."),
+    ('E', "###### #..... #..... #..... #####. #..... #..... #..... #..... ######"),
+    ('F', "###### #..... #..... #..... #####. #..... #..... #..... #..... #....."),
+    ('G', ".####. #....# #..... #..... #..... #..### #....# #....# #...## .###.#"),
+    ('H', "#....# #....# #....# #....# ###### #....# #....# #....# #....# #....#"),
+    ('J', "...### ....#. ....#. ....#. ....#. ....#. ....#. #...#. #...#. .###.."),
+    ('K', "#....# #...#. #..#.. #.#... ##.... ##.... #.#... #..#.. #...#. #....#"),
+    ('L', "#..... #..... #..... #..... #..... #..... #..... #..... #..... ######"),
+    ('N', "#....# ##...# ##...# #.#..# #.#..# #..#.# #..#.# #...## #...## #....#"),
+    ('P', "#####. #....# #....# #....# #####. #..... #..... #..... #..... #....."),
+    ('R', "#####. #....# #....# #....# #####. #..#.. #...#. #...#. #....# #....#"),
+    ('X', "#....# #....# .#..#. .#..#. ..##.. ..##.. .#..#. .#..#. #....# #....#"),
+    ('Z', "###### .....# .....# ....#. ...#.. ..#... .#.... #..... #..... ######"),
+];
+
+const SMALL_HEIGHT: usize = 6;
+const SMALL_FONT: [(char, &str); 18] = [
+    ('A', ".##. #..# #..# #### #..# #..#"),
+    ('B', "###. #..# ###. #..# #..# ###."),
+    ('C', ".##. #..# #... #... #..# .##."),
+    ('E', "#### #... ###. #... #... ####"),
+    ('F', "#### #... ###. #... #... #..."),
+    ('G', ".##. #..# #... #.## #..# .###"),
+    ('H', "#..# #..# #### #..# #..# #..#"),
+    ('I', "### .#. .#. .#. .#. ###"),
+    ('J', "..## ...# ...# ...# #..# .##."),
+    ('K', "#..# #.#. ##.. #.#. #.#. #..#"),
+    ('L', "#... #... #... #... #... ####"),
+    ('O', ".##. #..# #..# #..# #..# .##."),
+    ('P', "###. #..# #..# ###. #... #..."),
+    ('R', "###. #..# #..# ###. #.#. #..#"),
+    ('S', ".### #... #... .##. ...# ###."),
+    ('U', "#..# #..# #..# #..# #..# .##."),
+    ('Y', "#...# #...# .#.#. ..#.. ..#.. ..#.."),
+    ('Z', "#### ...# ..#. .#.. #... ####"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OcrError {
+    NoPoints,
+    UnsupportedHeight(usize),
+    // Which glyph from the left couldn't be read, and how it looked
+    UnknownGlyph { index: usize, glyph: String },
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OcrError::NoPoints => write!(f, "there are no points to read"),
+            OcrError::UnsupportedHeight(height) => write!(
+                f,
+                "letters are {} rows tall, only {} and {} row fonts are known",
+                height, SMALL_HEIGHT, LARGE_HEIGHT
+            ),
+            OcrError::UnknownGlyph { index, glyph } => write!(f, "glyph {} isn't a known letter:\n{}", index + 1, glyph),
+        }
+    }
+}
+
+// Reads the letters the points spell out, in whichever font matches their
+// height
+pub fn read(points: &[(i64, i64)]) -> Result<String, OcrError> {
+    if points.is_empty() {
+        return Err(OcrError::NoPoints);
+    }
+
+    let lit: HashSet<(i64, i64)> = points.iter().cloned().collect();
+    let min_x = points.iter().map(|p| p.0).min().unwrap();
+    let max_x = points.iter().map(|p| p.0).max().unwrap();
+    let min_y = points.iter().map(|p| p.1).min().unwrap();
+    let max_y = points.iter().map(|p| p.1).max().unwrap();
+
+    let height = (max_y - min_y + 1) as usize;
+    let font: &[(char, &str)] = match height {
+        LARGE_HEIGHT => &LARGE_FONT,
+        SMALL_HEIGHT => &SMALL_FONT,
+        _ => return Err(OcrError::UnsupportedHeight(height)),
+    };
+
+    let column = |x: i64| -> String {
+        (min_y..=max_y)
+            .map(|y| if lit.contains(&(x, y)) { '#' } else { '.' })
+            .collect()
+    };
+
+    let mut glyphs: Vec<Vec<String>> = Vec::new();
+    let mut glyph: Vec<String> = Vec::new();
+    for x in min_x..=max_x + 1 {
+        let col = column(x);
+        if col.contains('#') {
+            glyph.push(col);
+        } else if !glyph.is_empty() {
+            glyphs.push(std::mem::take(&mut glyph));
+        }
+    }
+
+    glyphs
+        .iter()
+        .enumerate()
+        .map(|(index, columns)| {
+            let rows: Vec<String> = (0..height)
+                .map(|y| columns.iter().map(|c| c.as_bytes()[y] as char).collect())
+                .collect();
+            let pattern = rows.join(" ");
+
+            font.iter()
+                .find(|(_, glyph)| *glyph == pattern)
+                .map(|(letter, _)| *letter)
+                .ok_or(OcrError::UnknownGlyph {
+                    index,
+                    glyph: rows.join("\n"),
+                })
+        })
+        .collect()
+}
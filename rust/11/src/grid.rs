@@ -0,0 +1,56 @@
+use crate::summed::SummedArea;
+
+// A cell's power from its 1 based coordinates and the grid serial number
+pub type PowerFn = fn(i64, i64, i64) -> i64;
+
+// The puzzle's: the hundreds digit of ((x + 10) * y + serial) * (x + 10),
+// less 5
+pub fn puzzle_power(x: i64, y: i64, serial: i64) -> i64 {
+    let rack_id = x + 10;
+    (rack_id * y + serial) * rack_id / 100 % 10 - 5
+}
+
+pub struct FuelGrid {
+    pub width: usize,
+    pub height: usize,
+    pub serial: i64,
+    power: PowerFn,
+}
+
+impl FuelGrid {
+    // The puzzle's 300 x 300 grid
+    pub fn new(serial: i64) -> FuelGrid {
+        FuelGrid::with_power(serial, puzzle_power)
+    }
+
+    // A 300 x 300 grid whose cells get their power some other way
+    pub fn with_power(serial: i64, power: PowerFn) -> FuelGrid {
+        FuelGrid {
+            width: 300,
+            height: 300,
+            serial,
+            power,
+        }
+    }
+
+    pub fn size(mut self, width: usize, height: usize) -> FuelGrid {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    pub fn power(&self, x: usize, y: usize) -> i64 {
+        (self.power)(x as i64, y as i64, self.serial)
+    }
+
+    // Row by row from the top, so levels[y - 1][x - 1] is cell (x, y)
+    pub fn levels(&self) -> Vec<Vec<i64>> {
+        (1..=self.height)
+            .map(|y| (1..=self.width).map(|x| self.power(x, y)).collect())
+            .collect()
+    }
+
+    pub fn summed_area(&self) -> SummedArea {
+        SummedArea::new(&self.levels())
+    }
+}
@@ -0,0 +1,48 @@
+use crate::grid::FuelGrid;
+use crate::summed::Square;
+
+const OUTLINE: [u8; 3] = [60, 255, 60];
+
+// Negative power fades from black to blue and positive to red, each as
+// strong as the most extreme level on its side of zero
+fn power_colour(power: i64, lowest: i64, highest: i64) -> [u8; 3] {
+    if power < 0 {
+        [0, 0, (power * 255 / lowest.min(-1)) as u8]
+    } else {
+        [(power * 255 / highest.max(1)) as u8, 0, 0]
+    }
+}
+
+// PPM of every cell's power level, each cell a scale x scale block of
+// pixels, with the square outlined if there is one
+pub fn render(grid: &FuelGrid, square: Option<Square>, scale: usize) -> Vec<u8> {
+    let levels = grid.levels();
+    let lowest = levels.iter().flatten().copied().min().unwrap_or(0);
+    let highest = levels.iter().flatten().copied().max().unwrap_or(0);
+    let scale = scale.max(1);
+    let (width, height) = (grid.width * scale, grid.height * scale);
+
+    // The outline runs around the inside edge of the square's pixels
+    let outlined = |px: usize, py: usize| {
+        square.is_some_and(|s| {
+            let (left, top) = ((s.x - 1) * scale, (s.y - 1) * scale);
+            let (right, bottom) = (left + s.size * scale - 1, top + s.size * scale - 1);
+            (left..=right).contains(&px)
+                && (top..=bottom).contains(&py)
+                && (px == left || px == right || py == top || py == bottom)
+        })
+    };
+
+    let mut ppm = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    for py in 0..height {
+        for px in 0..width {
+            ppm.extend(if outlined(px, py) {
+                OUTLINE
+            } else {
+                power_colour(levels[py / scale][px / scale], lowest, highest)
+            });
+        }
+    }
+
+    ppm
+}
@@ -3,106 +3,47 @@ use std::env;
 use std::fs;
 use std::time::Instant;
 
-fn part1(contents: String) -> String {
-    let serial: i64 = contents.trim().parse().unwrap();
-    let power_levels: Vec<Vec<i64>> = Vec::from_iter(
-        (1..=300).map(|y| {
-            Vec::from_iter(
-                (1..=300).map(|x| {
-                    let rack_id = x + 10;
-                    let mut power = rack_id * y;
-                    power += serial;
-                    power *= rack_id;
-                    power = (power / 100) % 10;
-                    power -= 5;
-                    power
-                })
-            )
-        })
-    );
+mod grid;
+mod heatmap;
+mod summed;
 
-    let mut max_power = 0;
-    let mut max_x = 0;
-    let mut max_y = 0;
-    for y in 0..298 {
-        for x in 0..298 {
-            let mut total_power = 0;
-            for i in 0..3 {
-                for j in 0..3 {
-                    total_power += power_levels[y + j][x + i];
-                }
-            }
+use grid::FuelGrid;
+use summed::Square;
 
-            if total_power > max_power {
-                max_power = total_power;
-                max_x = x + 1;
-                max_y = y + 1;
-            }
-        }
-    }
+fn part1(contents: String) -> String {
+    let grid = FuelGrid::new(parse_serial(&contents));
+    let best = grid.summed_area().best(3).expect("Should have been room for a 3x3 square");
 
-    return format!("{},{}", max_x, max_y);
+    return format!("{},{}", best.x, best.y);
 }
 
 fn part2(contents: String) -> String {
-    let serial: i64 = contents.trim().parse().unwrap();
-    let check_back: i64 = 1;
-
-    let power_levels: Vec<Vec<i64>> = Vec::from_iter(
-        (1..=300).map(|y| {
-            Vec::from_iter(
-                (1..=300).map(|x| {
-                    let rack_id = x + 10;
-                    let mut power = rack_id * y;
-                    power += serial;
-                    power *= rack_id;
-                    power = (power / 100) % 10;
-                    power -= 5;
-                    power
-                })
-            )
-        })
-    );
-
-    let mut max_power = 0;
-    let mut max_x = 0;
-    let mut max_y = 0;
-    let mut max_size = 0;
-    let mut increased: Vec<bool> = vec![];
-
-    for size in 3..=300 {
-        for y in 0..(300 - size) {
-            for x in 0..(300 - size) {
-                let mut total_power = 0;
-                for i in 0..size {
-                    for j in 0..size {
-                        total_power += power_levels[y + j][x + i];
-                    }
-                }
+    let grid = FuelGrid::new(parse_serial(&contents));
+    // The smallest size wins a tie
+    let best = best_per_size(&grid)
+        .into_iter()
+        .reduce(|best, square| if square.power > best.power { square } else { best })
+        .expect("Should have been at least one cell");
+
+    return format!("{},{},{}", best.x, best.y, best.size);
+}
 
-                if total_power > max_power {
-                    max_power = total_power;
-                    max_x = x + 1;
-                    max_y = y + 1;
-                    max_size = size;
-                    increased.push(true);
-                }
-            }
-        }
+fn parse_serial(contents: &str) -> i64 {
+    contents.trim().parse().expect("Should have been a serial number")
+}
 
-        if increased.len() == size as usize - 3 {
-            increased.push(false);
-        }
+// The best square of every size that fits, found exactly
+fn best_per_size(grid: &FuelGrid) -> Vec<Square> {
+    grid.summed_area().best_per_size()
+}
 
-        if increased[increased.len() - check_back as usize..increased.len()]
-            .iter()
-            .all(|&x| x == false)
-        {
-            break;
-        }
+fn sizes_table(squares: &[Square]) -> String {
+    let mut table = format!("{:>4} {:>4} {:>4} {:>6}", "Size", "X", "Y", "Power");
+    for square in squares {
+        table += &format!("\n{:>4} {:>4} {:>4} {:>6}", square.size, square.x, square.y, square.power);
     }
 
-    return format!("{},{},{}", max_x, max_y, max_size);
+    table
 }
 
 #[cfg(test)]
@@ -134,15 +75,79 @@ mod tests {
 
         assert_eq!(part2(contents), "232,251,12".to_string());
     }
+
+    #[test]
+    fn summed_test() {
+        // Uneven, so a square hugging the last row or column can win
+        let levels: Vec<Vec<i64>> = (0..13)
+            .map(|y| (0..11).map(|x| (x * 7 + y * 13 + x * y) % 11 - 5).collect())
+            .collect();
+        let table = summed::SummedArea::new(&levels);
+
+        for size in 1..=11 {
+            let mut brute: Option<Square> = None;
+            for y in 0..=13 - size {
+                for x in 0..=11 - size {
+                    let power = (y..y + size).flat_map(|j| (x..x + size).map(move |i| (i, j))).map(|(i, j)| levels[j][i]).sum();
+                    if brute.is_none_or(|b| power > b.power) {
+                        brute = Some(Square { x: x + 1, y: y + 1, size, power });
+                    }
+                }
+            }
+            assert_eq!(table.best(size), brute);
+        }
+        assert_eq!(table.best(12), None);
+        assert_eq!(table.best_per_size().len(), 11);
+
+        let corner: Vec<Vec<i64>> = (0..5).map(|y| (0..5).map(|x| if x == 4 && y == 4 { 9 } else { -1 }).collect()).collect();
+        assert_eq!(summed::SummedArea::new(&corner).best(1), Some(Square { x: 5, y: 5, size: 1, power: 9 }));
+
+        let sizes = best_per_size(&FuelGrid::new(18));
+        assert_eq!(sizes.len(), 300);
+        assert_eq!(sizes[2], Square { x: 33, y: 45, size: 3, power: 29 });
+        assert_eq!(sizes[15], Square { x: 90, y: 269, size: 16, power: 113 });
+        assert_eq!(part2("42".to_string()), "232,251,12".to_string());
+    }
+
+    #[test]
+    fn grid_test() {
+        for (x, y, serial, power) in [(3, 5, 8, 4), (122, 79, 57, -5), (217, 196, 39, 0), (101, 153, 71, 4)] {
+            assert_eq!(FuelGrid::new(serial).power(x, y), power);
+        }
+
+        let grid = FuelGrid::new(18).size(40, 20);
+        let levels = grid.levels();
+        assert_eq!((levels.len(), levels[0].len()), (20, 40));
+        assert_eq!(levels[4][2], grid.power(3, 5));
+        let best = grid.summed_area().best(3).unwrap();
+        assert!(best.x + 2 <= 40 && best.y + 2 <= 20);
+        assert_eq!(best_per_size(&grid).len(), 20);
+
+        let diagonal = FuelGrid::with_power(18, |x, y, _| if x == y { 1 } else { -1 }).size(6, 6);
+        assert_eq!(diagonal.power(4, 4), 1);
+        assert_eq!(diagonal.summed_area().best(6).unwrap().power, -24);
+
+        let square = Square { x: 2, y: 3, size: 2, power: 0 };
+        let ppm = heatmap::render(&FuelGrid::new(18).size(5, 4), Some(square), 3);
+        let header = "P6\n15 12\n255\n".len();
+        assert_eq!(ppm.len(), header + 15 * 12 * 3);
+        let pixel = |px: usize, py: usize| &ppm[header + (py * 15 + px) * 3..header + (py * 15 + px) * 3 + 3];
+        assert_eq!(pixel(3, 6), [60, 255, 60]);
+        assert_eq!(pixel(8, 11), [60, 255, 60]);
+        assert_ne!(pixel(4, 7), [60, 255, 60]);
+        assert_ne!(pixel(9, 6), [60, 255, 60]);
+    }
 }
 fn main() {
     let args: Vec<String> = env::args().collect();
+    let (flags, paths): (Vec<String>, Vec<String>) =
+        args.iter().skip(1).cloned().partition(|arg| arg.starts_with("--"));
     let year = "2018".to_string();
     let day = "11".to_string();
 
     let root = env::current_dir().unwrap();
-    let path_str = if args.len() > 1 {
-        args[1].clone()
+    let path_str = if !paths.is_empty() {
+        paths[0].clone()
     } else if root.ends_with(format!("{}", day)) {
         format!("../../../Inputs/{}_{}.txt", year, day)
     } else {
@@ -150,9 +155,13 @@ fn main() {
     };
 
 
-    let contents = fs::read_to_string(if args.len() > 1 {path_str} else {RelativePath::new(&path_str).to_path(&root).display().to_string()})
+    let contents = fs::read_to_string(if !paths.is_empty() {path_str} else {RelativePath::new(&path_str).to_path(&root).display().to_string()})
         .expect("Should have been able to read the file");
 
+    let setting = |name: &str| flags.iter().find_map(|flag| flag.strip_prefix(name));
+    let number = |name: &str, default: usize| setting(name).map_or(default, |n| n.parse::<usize>().expect("Should have been a number"));
+    let grid = FuelGrid::new(parse_serial(&contents)).size(number("--width=", 300), number("--height=", 300));
+
     let part1_timer = Instant::now();
     println!(
         "\nPart 1:\nLargest power: {}\nRan in {:.5?}",
@@ -166,4 +175,18 @@ fn main() {
         part2(contents.clone()),
         part2_timer.elapsed()
     );
-}
\ No newline at end of file
+
+    for flag in &flags {
+        match flag.split_once('=') {
+            Some(("--heatmap", path)) => {
+                let size = number("--square=", 3);
+                let best = grid.summed_area().best(size);
+                fs::write(path, heatmap::render(&grid, best, number("--scale=", 2))).expect("Should have been able to write the image");
+                println!("\nWrote power levels to {}", path);
+            }
+            Some(("--width", _)) | Some(("--height", _)) | Some(("--square", _)) | Some(("--scale", _)) => {}
+            _ if flag == "--sizes" => println!("\nBest square of each size:\n{}", sizes_table(&best_per_size(&grid))),
+            _ => panic!("Unknown option {}", flag),
+        }
+    }
+}
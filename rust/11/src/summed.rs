@@ -0,0 +1,66 @@
+// A summed-area table: sums[y][x] is the total of every cell above and left
+// of (x, y), so the total of any square comes from four lookups
+pub struct SummedArea {
+    width: usize,
+    height: usize,
+    sums: Vec<Vec<i64>>,
+}
+
+// The best square of one size, by its 1 based top-left corner
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Square {
+    pub x: usize,
+    pub y: usize,
+    pub size: usize,
+    pub power: i64,
+}
+
+impl SummedArea {
+    pub fn new(levels: &[Vec<i64>]) -> SummedArea {
+        let height = levels.len();
+        let width = levels.first().map_or(0, |row| row.len());
+        let mut sums: Vec<Vec<i64>> = vec![vec![0; width + 1]; height + 1];
+        for y in 0..height {
+            for x in 0..width {
+                sums[y + 1][x + 1] = levels[y][x] + sums[y][x + 1] + sums[y + 1][x] - sums[y][x];
+            }
+        }
+
+        SummedArea { width, height, sums }
+    }
+
+    // Total of the size x size square whose top-left cell is (x, y), 0 based
+    fn square(&self, x: usize, y: usize, size: usize) -> i64 {
+        self.sums[y + size][x + size] - self.sums[y][x + size] - self.sums[y + size][x] + self.sums[y][x]
+    }
+
+    // Every position a square this size fits, all the way to the last row and
+    // column. Ties go to the topmost, then leftmost, square.
+    pub fn best(&self, size: usize) -> Option<Square> {
+        if size == 0 || size > self.width || size > self.height {
+            return None;
+        }
+
+        let mut best: Option<Square> = None;
+        for y in 0..=self.height - size {
+            for x in 0..=self.width - size {
+                let power = self.square(x, y, size);
+                if best.is_none_or(|b| power > b.power) {
+                    best = Some(Square {
+                        x: x + 1,
+                        y: y + 1,
+                        size,
+                        power,
+                    });
+                }
+            }
+        }
+
+        best
+    }
+
+    // The best square of every size from 1 up to the whole grid
+    pub fn best_per_size(&self) -> Vec<Square> {
+        (1..=self.width.min(self.height)).filter_map(|size| self.best(size)).collect()
+    }
+}
@@ -0,0 +1,88 @@
+use crate::pots::{Pots, Rules};
+use std::collections::HashMap;
+use std::fmt;
+
+// How many generations to look through for a repeat before giving up. Rows
+// that keep growing never repeat, so the search can't go on forever.
+pub const SEARCH_LIMIT: usize = 10_000;
+
+// From generation `start` on, every `period` generations the row has the
+// same shape again, `shift` pots further right
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub period: usize,
+    pub shift: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoCycle {
+    pub limit: usize,
+}
+
+impl fmt::Display for NoCycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "no repeating pattern within {} steps to extrapolate from", self.limit)
+    }
+}
+
+// Every generation's plant count and pot number sum, as far as was run
+pub struct History {
+    pub counts: Vec<i64>,
+    pub sums: Vec<i64>,
+    pub cycle: Option<Cycle>,
+}
+
+// Runs until a shape comes round again or the generations run out. Rules
+// don't care where a shape sits, so once one repeats, everything after it
+// repeats too, each time moved along by the same shift.
+pub fn run(mut pots: Pots, rules: &Rules, generations: usize) -> History {
+    let mut seen: HashMap<Vec<u64>, (usize, i64)> = HashMap::new();
+    let mut history = History {
+        counts: Vec::new(),
+        sums: Vec::new(),
+        cycle: None,
+    };
+
+    for generation in 0..=generations {
+        history.counts.push(pots.count());
+        history.sums.push(pots.sum());
+
+        let (leftmost, shape) = pots.shape();
+        if let Some(&(start, first_leftmost)) = seen.get(&shape) {
+            history.cycle = Some(Cycle {
+                start,
+                period: generation - start,
+                shift: leftmost - first_leftmost,
+            });
+            break;
+        }
+        seen.insert(shape, (generation, leftmost));
+
+        if generation < generations {
+            pots = pots.step(rules);
+        }
+    }
+
+    history
+}
+
+// The sum after any number of generations, however many that is, as long
+// as the pattern repeats within `limit` generations or there are no more
+// generations than that. A generation in the cycle is the one `repeats`
+// periods earlier, moved `repeats` shifts along, which moves every plant's
+// pot number by that much.
+pub fn sum_after(pots: Pots, rules: &Rules, generations: u64, limit: usize) -> Result<i64, NoCycle> {
+    let history = run(pots, rules, generations.min(limit as u64) as usize);
+    if generations < history.sums.len() as u64 {
+        return Ok(history.sums[generations as usize]);
+    }
+    let Some(cycle) = history.cycle else {
+        return Err(NoCycle { limit });
+    };
+
+    let since_start = generations - cycle.start as u64;
+    let repeats = (since_start / cycle.period as u64) as i64;
+    let earlier = cycle.start + (since_start % cycle.period as u64) as usize;
+    Ok(history.sums[earlier] + repeats * cycle.shift * history.counts[earlier])
+}
@@ -1,153 +1,213 @@
 use relative_path::RelativePath;
-use std::collections::HashSet;
 use std::env;
 use std::fs;
 use std::time::Instant;
 
-fn part1(contents: String) -> i64 {
-    let mut plants: HashSet<i64> = HashSet::from_iter(
-        contents
-            .lines()
-            .nth(0)
-            .unwrap()
-            .split(" ")
-            .nth(2)
-            .unwrap()
-            .chars()
-            .enumerate()
-            .filter(|(_, c)| *c == '#')
-            .map(|(i, _)| i as i64),
-    );
-    let rules: HashSet<String> = HashSet::from_iter(
-        contents
-            .lines()
-            .skip(2)
-            .filter(|l| l.split(" => ").nth(1).unwrap() == "#")
-            .map(|l| l.split(" =>").nth(0).unwrap().to_string()),
-    );
+mod cycle;
+mod pots;
+
+use pots::{Pots, Rules};
 
+fn part1(contents: String) -> i64 {
+    let (mut pots, rules) = parse(&contents);
     for _ in 0..20 {
-        let mut new_plants: HashSet<i64> = HashSet::new();
-        let min = *plants.iter().min().unwrap() - 2;
-        let max = *plants.iter().max().unwrap() + 2;
-        let mut pos_str: String = (-2..=2)
-            .map(|i| {
-                if plants.contains(&(min + i)) {
-
-                    '#'
-                } else {
-                    '.'
-                }
-            })
-            .collect();
+        pots = pots.step(&rules);
+    }
 
-        for i in min..=max {
-            if rules.contains(&pos_str) {
-                new_plants.insert(i);
-            }
+    return pots.sum();
+}
 
-            pos_str = pos_str.chars().skip(1).collect::<String>()
-                + if plants.contains(&(i + 3)) { "#" } else { "." };
-        }
+fn part2(contents: String, limit: usize) -> Result<i64, cycle::NoCycle> {
+    let (pots, rules) = parse(&contents);
+    return cycle::sum_after(pots, &rules, 50000000000, limit);
+}
 
-        plants = new_plants;
-    }
+// "initial state: #..#.#..##" and then one rule a line
+fn parse(contents: &str) -> (Pots, Rules) {
+    let initial = contents
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("initial state:"))
+        .expect("Should have been the initial state");
 
-    return plants.iter().sum();
+    (Pots::parse(initial), Rules::parse(contents.lines().skip(2)))
 }
 
-fn part2(contents: String) -> i64 {
-    let mut plants: HashSet<i64> = HashSet::from_iter(
-        contents
-            .lines()
-            .nth(0)
-            .unwrap()
-            .split(" ")
-            .nth(2)
-            .unwrap()
-            .chars()
-            .enumerate()
-            .filter(|(_, c)| *c == '#')
-            .map(|(i, _)| i as i64),
-    );
-    let rules: HashSet<String> = HashSet::from_iter(
-        contents
-            .lines()
-            .skip(2)
-            .filter(|l| l.split(" => ").nth(1).unwrap() == "#")
-            .map(|l| l.split(" =>").nth(0).unwrap().to_string()),
-    );
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
 
-    let mut steps: i64 = 0;
-    let mut deltas: Vec<i64> = Vec::new();
-    let mut p_sum: i64 = plants.iter().sum();
-    let check_back: usize = 3;
-
-    while steps < 50000000000 {
-        let mut new_plants: HashSet<i64> = HashSet::new();
-        let min = *plants.iter().min().unwrap() - 2;
-        let max = *plants.iter().max().unwrap() + 2;
-        let mut pos_str: String = (-2..=2)
-            .map(|i| {
-                if plants.contains(&(min + i)) {
-                    '#'
-                } else {
-                    '.'
-                }
-            })
-            .collect();
+    #[test]
+    fn p1_test() {
+        let contents =
+            fs::read_to_string("example.txt").expect("Should have been able to read the file");
 
-        for i in min..=max {
-            if rules.contains(&pos_str) {
-                new_plants.insert(i);
-            }
+        assert_eq!(part1(contents), 325);
+    }
 
-            pos_str = pos_str.chars().skip(1).collect::<String>()
-                + if plants.contains(&(i + 3)) { "#" } else { "." };
-        }
+    const EXAMPLE: &str = "initial state: #..#.#..##......###...###
+
+...## => #
+..#.. => #
+.#... => #
+.#.#. => #
+.#.## => #
+.##.. => #
+.#### => #
+#.#.# => #
+#.### => #
+##.#. => #
+##.## => #
+###.. => #
+###.# => #
+####. => #";
 
-        plants = new_plants;
-        steps += 1;
+    // The string by string way, one pot at a time
+    fn naive_step(plants: &HashSet<i64>, growing: &HashSet<String>) -> HashSet<i64> {
+        let (min, max) = (*plants.iter().min().unwrap(), *plants.iter().max().unwrap());
+        (min - 2..=max + 2)
+            .filter(|&i| {
+                let window: String = (i - 2..=i + 2)
+                    .map(|j| if plants.contains(&j) { '#' } else { '.' })
+                    .collect();
+                growing.contains(&window)
+            })
+            .collect()
+    }
 
-        let sum = plants.iter().sum();
-        deltas.push(sum - p_sum);
+    #[test]
+    fn pots_test() {
+        assert_eq!(part1(EXAMPLE.to_string()), 325);
 
-        p_sum = sum;
+        // Each rule set grows a plant for the patterns whose bits of a seed
+        // are set, over a row wider than a word so plants cross word edges
+        let initial = "#..#.#..##......###...###.#.##...#####..#.#......##.#.#.#..###..#.#...##.#";
+        for seed in [
+            0x2b5e_96d4u32,
+            0x9e37_79b8,
+            0x0f0f_f0f0,
+            0x7fff_fffe,
+            0x5555_5554,
+        ] {
+            let growing: HashSet<String> = (1..32)
+                .filter(|&pattern| seed >> pattern & 1 == 1)
+                .map(|pattern| {
+                    (0..5)
+                        .map(|k| {
+                            if pattern >> (4 - k) & 1 == 1 {
+                                '#'
+                            } else {
+                                '.'
+                            }
+                        })
+                        .collect()
+                })
+                .collect();
+            let lines: Vec<String> = growing
+                .iter()
+                .map(|pattern| format!("{} => #", pattern))
+                .collect();
+            let rules = Rules::parse(lines.iter().map(|line| line.as_str()));
 
-        if deltas.len() > check_back
-            && (0..check_back)
-                .map(|i| deltas[deltas.len() - 1 - i])
-                .collect::<HashSet<i64>>()
-                .len()
-                == 1
-        {
-            break;
+            let mut pots = Pots::parse(initial);
+            let mut plants: HashSet<i64> = initial
+                .chars()
+                .enumerate()
+                .filter(|&(_, c)| c == '#')
+                .map(|(i, _)| i as i64)
+                .collect();
+            for _ in 0..150 {
+                if plants.is_empty() {
+                    break;
+                }
+                pots = pots.step(&rules);
+                plants = naive_step(&plants, &growing);
+                assert_eq!(pots.sum(), plants.iter().sum::<i64>());
+            }
         }
     }
 
-    return p_sum + (50000000000 - steps) * deltas[deltas.len() - 1];
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    #[should_panic(expected = "Should have been five pots of # or . in \"#.#.#. => #\"")]
+    fn long_rule_test() {
+        Rules::parse(["#.#.# => #", "#.#.#. => #"].into_iter());
+    }
 
     #[test]
-    fn p1_test() {
-        let contents =
-            fs::read_to_string("example.txt").expect("Should have been able to read the file");
+    fn cycle_test() {
+        let (pots, rules) = parse(EXAMPLE);
+        let history = cycle::run(pots.clone(), &rules, 1000);
+        let cycle = history.cycle.unwrap();
+        assert_eq!((cycle.period, cycle.shift), (1, 1));
+        assert_eq!(cycle::sum_after(pots.clone(), &rules, 20, 1000).unwrap(), 325);
+        let late = cycle::sum_after(pots.clone(), &rules, 50000000000, 1000).unwrap();
+        assert_eq!(late - cycle::sum_after(pots, &rules, 49999999999, 1000).unwrap(), history.counts[cycle.start]);
 
-        assert_eq!(part1(contents), 325);
+        // Rows that blink or drift with periods above 1 and settle at
+        // different times, checked against just running them
+        let initial = "#..#.#..##......###...###";
+        let mut periods: HashSet<usize> = HashSet::new();
+        for seed in 1..150u32 {
+            let table = seed.wrapping_mul(0x9e37_79b9) & !1;
+            let lines: Vec<String> = (1..32)
+                .filter(|&pattern| table >> pattern & 1 == 1)
+                .map(|pattern| format!("{} => #", (0..5).map(|k| if pattern >> (4 - k) & 1 == 1 { '#' } else { '.' }).collect::<String>()))
+                .collect();
+            let rules = Rules::parse(lines.iter().map(|line| line.as_str()));
+            let pots = Pots::parse(initial);
+
+            let history = cycle::run(pots.clone(), &rules, 300);
+            let Some(cycle) = history.cycle else {
+                continue;
+            };
+            periods.insert(cycle.period);
+
+            let mut row = pots.clone();
+            for generation in 0..=300u64 {
+                if generation % 23 == 0 || generation == 300 {
+                    assert_eq!(cycle::sum_after(pots.clone(), &rules, generation, 300).unwrap(), row.sum());
+                }
+                row = row.step(&rules);
+            }
+        }
+        assert!(periods.iter().any(|&period| period > 1));
+
+        // A pot has a plant when exactly one of its neighbours did, which
+        // draws an ever wider Sierpinski triangle that never repeats
+        let lines: Vec<String> = (0..32usize)
+            .filter(|&pattern| (pattern >> 3 & 1) != (pattern >> 1 & 1))
+            .map(|pattern| {
+                let pots: String = (0..5)
+                    .map(|k| if pattern >> (4 - k) & 1 == 1 { '#' } else { '.' })
+                    .collect();
+                format!("{} => #", pots)
+            })
+            .collect();
+        let rules = Rules::parse(lines.iter().map(|line| line.as_str()));
+        let pots = Pots::parse("#");
+        assert_eq!(cycle::run(pots.clone(), &rules, 200).cycle, None);
+        assert_eq!(
+            cycle::sum_after(pots.clone(), &rules, 50000000000, 200),
+            Err(cycle::NoCycle { limit: 200 })
+        );
+        let mut row = pots.clone();
+        for _ in 0..150 {
+            row = row.step(&rules);
+        }
+        assert_eq!(cycle::sum_after(pots, &rules, 150, 200), Ok(row.sum()));
     }
 }
 fn main() {
     let args: Vec<String> = env::args().collect();
+    let (flags, paths): (Vec<String>, Vec<String>) =
+        args.iter().skip(1).cloned().partition(|arg| arg.starts_with("--"));
     let year = "2018".to_string();
     let day = "12".to_string();
 
     let root = env::current_dir().unwrap();
-    let path_str = if args.len() > 1 {
-        args[1].clone()
+    let path_str = if !paths.is_empty() {
+        paths[0].clone()
     } else if root.ends_with(format!("{}", day)) {
         format!("../../../Inputs/{}_{}.txt", year, day)
     } else {
@@ -155,7 +215,7 @@ fn main() {
     };
 
 
-    let contents = fs::read_to_string(if args.len() > 1 {path_str} else {RelativePath::new(&path_str).to_path(&root).display().to_string()})
+    let contents = fs::read_to_string(if !paths.is_empty() {path_str} else {RelativePath::new(&path_str).to_path(&root).display().to_string()})
         .expect("Should have been able to read the file");
 
     let part1_timer = Instant::now();
@@ -165,10 +225,39 @@ fn main() {
         part1_timer.elapsed()
     );
 
+    let limit = flags
+        .iter()
+        .find_map(|flag| flag.strip_prefix("--search-limit="))
+        .map_or(cycle::SEARCH_LIMIT, |n| n.parse::<usize>().expect("Should have been a number"));
+
     let part2_timer = Instant::now();
     println!(
         "\nPart 2:\nSum of plant pot numbers after 50000000000 steps: {}\nRan in {:.5?}",
-        part2(contents.clone()),
+        part2(contents.clone(), limit).map_or_else(|error| error.to_string(), |sum| sum.to_string()),
         part2_timer.elapsed()
     );
-}
\ No newline at end of file
+
+    let (pots, rules) = parse(&contents);
+    for flag in &flags {
+        match flag.split_once('=') {
+            Some(("--generations", n)) => {
+                let generations = n.parse::<u64>().expect("Should have been a number");
+                let sum = cycle::sum_after(pots.clone(), &rules, generations, limit);
+                println!(
+                    "\nSum of plant pot numbers after {} steps: {}",
+                    generations,
+                    sum.map_or_else(|error| error.to_string(), |sum| sum.to_string())
+                );
+            }
+            Some(("--search-limit", _)) => {}
+            _ if flag == "--cycle" => match cycle::run(pots.clone(), &rules, limit).cycle {
+                Some(cycle) => println!(
+                    "\nFrom step {} the pattern repeats every {} steps, {} pots to the right",
+                    cycle.start, cycle.period, cycle.shift
+                ),
+                None => println!("\nThe pattern doesn't repeat within {} steps", limit),
+            },
+            _ => panic!("Unknown option {}", flag),
+        }
+    }
+}
@@ -0,0 +1,151 @@
+// Which five pot patterns grow a plant. Pattern "LLCRR" is the number
+// whose bits, most significant first, are those pots with # as 1, and bit n
+// of the table is whether pattern n grows a plant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rules {
+    table: u32,
+}
+
+impl Rules {
+    // The "..#.# => #" lines; any pattern not listed leaves the pot empty
+    pub fn parse<'a>(lines: impl Iterator<Item = &'a str>) -> Rules {
+        let mut table = 0;
+        for line in lines.filter(|l| !l.trim().is_empty()) {
+            let (pattern, result) = line.split_once(" => ").expect("Should have been a rule");
+            let pattern = pattern.trim();
+            if pattern.len() != 5 || !pattern.chars().all(|c| c == '#' || c == '.') {
+                panic!("Should have been five pots of # or . in \"{}\"", line);
+            }
+
+            match result.trim() {
+                "#" => table |= 1 << pattern_index(pattern),
+                "." => {}
+                _ => panic!("Should have been # or . after => in \"{}\"", line),
+            }
+        }
+
+        // Every empty pot sprouting at once would fill the endless row
+        if table & 1 == 1 {
+            panic!("Should have been a rule leaving empty pots empty, not \"..... => #\"");
+        }
+
+        Rules { table }
+    }
+
+    fn grows(&self, pattern: usize) -> bool {
+        self.table >> pattern & 1 == 1
+    }
+}
+
+fn pattern_index(pattern: &str) -> usize {
+    pattern.chars().fold(0, |index, c| index << 1 | (c == '#') as usize)
+}
+
+// The row as a bitset: bit i of words[w] is pot origin + 64 * w + i. There
+// are never empty words at either end, so an empty row has no words.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pots {
+    words: Vec<u64>,
+    origin: i64,
+}
+
+impl Pots {
+    // "#..#.#..##", the first character being pot 0
+    pub fn parse(state: &str) -> Pots {
+        let mut words: Vec<u64> = vec![0; state.trim().len().div_ceil(64)];
+        for (i, c) in state.trim().chars().enumerate() {
+            if c == '#' {
+                words[i / 64] |= 1 << (i % 64);
+            }
+        }
+
+        let mut pots = Pots { words, origin: 0 };
+        pots.trim();
+        pots
+    }
+
+    fn trim(&mut self) {
+        let leading = self.words.iter().take_while(|&&w| w == 0).count();
+        self.words.drain(..leading);
+        self.origin += 64 * leading as i64;
+        while self.words.last() == Some(&0) {
+            self.words.pop();
+        }
+    }
+
+    fn word(&self, w: i64) -> u64 {
+        if w < 0 {
+            0
+        } else {
+            self.words.get(w as usize).copied().unwrap_or(0)
+        }
+    }
+
+    // Word w of the row as seen from `offset` pots to the right: bit i is
+    // pot origin + 64 * w + i + offset
+    fn shifted(&self, w: i64, offset: i64) -> u64 {
+        match offset {
+            0 => self.word(w),
+            1..=63 => self.word(w) >> offset | self.word(w + 1) << (64 - offset),
+            -63..=-1 => self.word(w) << -offset | self.word(w - 1) >> (64 + offset),
+            _ => unreachable!(),
+        }
+    }
+
+    // A generation 64 pots at a time. For each pattern that grows a plant,
+    // the pots whose neighbourhoods match it are the AND of the five shifted
+    // rows, each inverted where the pattern has an empty pot. Plants spread
+    // at most two pots a generation, so a word either side is enough room.
+    pub fn step(&self, rules: &Rules) -> Pots {
+        let growing: Vec<usize> = (0..32).filter(|&pattern| rules.grows(pattern)).collect();
+        let mut words: Vec<u64> = vec![0; self.words.len() + 2];
+        for (w, word) in words.iter_mut().enumerate() {
+            let w = w as i64 - 1;
+            let neighbours: [u64; 5] = [-2, -1, 0, 1, 2].map(|offset| self.shifted(w, offset));
+            for &pattern in &growing {
+                *word |= (0..5).fold(u64::MAX, |matching, k| {
+                    let wanted = pattern >> (4 - k) & 1 == 1;
+                    matching & if wanted { neighbours[k] } else { !neighbours[k] }
+                });
+            }
+        }
+
+        let mut next = Pots {
+            words,
+            origin: self.origin - 64,
+        };
+        next.trim();
+        next
+    }
+
+    pub fn count(&self) -> i64 {
+        self.words.iter().map(|w| w.count_ones() as i64).sum()
+    }
+
+    // Where the leftmost plant is, and the row's words shifted so that plant
+    // is bit 0. Rows with the same shape differ only by where they sit.
+    pub fn shape(&self) -> (i64, Vec<u64>) {
+        let Some(first) = self.words.first() else {
+            return (0, Vec::new());
+        };
+
+        let zeros = first.trailing_zeros() as i64;
+        let mut words: Vec<u64> = (0..self.words.len() as i64).map(|w| self.shifted(w, zeros)).collect();
+        while words.last() == Some(&0) {
+            words.pop();
+        }
+
+        (self.origin + zeros, words)
+    }
+
+    pub fn sum(&self) -> i64 {
+        self.words
+            .iter()
+            .enumerate()
+            .map(|(w, &word)| {
+                let base = self.origin + 64 * w as i64;
+                (0..64).filter(|i| word >> i & 1 == 1).map(|i| base + i).sum::<i64>()
+            })
+            .sum()
+    }
+}
@@ -5,19 +5,17 @@ use std::env;
 use std::fs;
 use std::time::Instant;
 
-fn part1(contents: String) -> i64 {
-    let area_re = Regex::new(r"(-?\d+),(-?\d+): (\d+)x(\d+)").unwrap();
+mod render;
+mod spatial;
+
+use spatial::{Rect, RTree};
 
+fn part1(contents: String) -> i64 {
     let mut claimed: HashSet<(i64, i64)> = HashSet::new();
     let mut overlaps: HashSet<(i64, i64)> = HashSet::new();
-    for line in contents.lines() {
-        let caps = area_re.captures(line).unwrap();
-        for y in (caps[2].parse::<i64>().unwrap())
-            ..(caps[2].parse::<i64>().unwrap() + caps[4].parse::<i64>().unwrap())
-        {
-            for x in (caps[1].parse::<i64>().unwrap())
-                ..(caps[1].parse::<i64>().unwrap() + caps[3].parse::<i64>().unwrap())
-            {
+    for (x0, y0, w, h) in parse_claims(&contents) {
+        for y in y0..(y0 + h) {
+            for x in x0..(x0 + w) {
                 if !claimed.insert((x, y)) {
                     overlaps.insert((x, y));
                 }
@@ -29,38 +27,39 @@ fn part1(contents: String) -> i64 {
 }
 
 fn part2(contents: String) -> i64 {
-    let area_re = Regex::new(r"(-?\d+),(-?\d+): (\d+)x(\d+)").unwrap();
-    let mut areas: Vec<(i64, i64, i64, i64)> = Vec::new();
-    for line in contents.lines() {
-        let caps = area_re.captures(line).unwrap();
-
-        areas.push((
-            caps[1].parse::<i64>().unwrap(),
-            caps[2].parse::<i64>().unwrap(),
-            caps[3].parse::<i64>().unwrap(),
-            caps[4].parse::<i64>().unwrap(),
-        ));
-    }
-
-    for i in 0..areas.len() {
-        let mut intersection = false;
-        for j in 0..areas.len() {
-            if i != j && intersects(areas[i], areas[j]) {
-                intersection = true;
-                break;
-            }
-        }
+    let tree = RTree::new(parse_claims(&contents));
+    tree.isolated().first().map_or(-1, |i| *i as i64 + 1)
+}
 
-        if !intersection {
-            return i as i64 + 1;
-        }
-    }
+fn parse_claims(contents: &str) -> Vec<Rect> {
+    let area_re = Regex::new(r"(-?\d+),(-?\d+): (\d+)x(\d+)").unwrap();
+    contents
+        .lines()
+        .map(|line| {
+            let caps = area_re.captures(line).unwrap();
+            (
+                caps[1].parse::<i64>().unwrap(),
+                caps[2].parse::<i64>().unwrap(),
+                caps[3].parse::<i64>().unwrap(),
+                caps[4].parse::<i64>().unwrap(),
+            )
+        })
+        .collect()
+}
 
-    return -1;
+fn claim_ids(indices: &[usize]) -> String {
+    indices
+        .iter()
+        .map(|i| format!("#{}", i + 1))
+        .collect::<Vec<String>>()
+        .join(", ")
 }
 
-fn intersects(a: (i64, i64, i64, i64), b: (i64, i64, i64, i64)) -> bool {
-    return a.0 < b.0 + b.2 && a.0 + a.2 > b.0 && a.1 < b.1 + b.3 && a.1 + a.3 > b.1;
+fn parse_nums(arg: &str) -> Vec<i64> {
+    arg
+        .split([',', 'x'])
+        .map(|n| n.trim().parse::<i64>().expect("Should have been a number"))
+        .collect()
 }
 
 #[cfg(test)]
@@ -82,15 +81,62 @@ mod tests {
 
         assert_eq!(part2(contents), 3);
     }
+
+    const EXAMPLE: &str = "#1 @ 1,3: 4x4\n#2 @ 3,1: 4x4\n#3 @ 5,5: 2x2";
+
+    #[test]
+    fn spatial_queries_test() {
+        let tree = RTree::new(parse_claims(EXAMPLE));
+
+        assert_eq!(tree.covering(3, 3), vec![0, 1]);
+        assert_eq!(tree.covering(0, 0), Vec::<usize>::new());
+        assert_eq!(tree.intersecting((4, 4, 2, 2)), vec![0, 1, 2]);
+        assert_eq!(tree.largest_overlap_cluster(), vec![0, 1]);
+        assert_eq!(tree.max_depth(), (2, (3, 3)));
+        assert_eq!(tree.isolated(), vec![2]);
+    }
+
+    #[test]
+    fn render_test() {
+        let claims = parse_claims(EXAMPLE);
+
+        assert_eq!(
+            render::ascii(&claims),
+            "........\n...2222.\n...2222.\n.11XX22.\n.11XX22.\n.111133.\n.111133.\n........"
+        );
+
+        let ppm = render::heatmap(&claims, 2).to_ppm();
+        assert!(ppm.starts_with(b"P6\n16 16\n255\n"));
+        assert_eq!(ppm.len(), b"P6\n16 16\n255\n".len() + 16 * 16 * 3);
+
+        let png = render::heatmap(&claims, 2).to_png();
+        assert!(png.starts_with(&[0x89, b'P', b'N', b'G']));
+        assert!(png.ends_with(&[b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]));
+    }
+
+    #[test]
+    fn spatial_many_claims_test() {
+        let claims: Vec<Rect> = (0..100).map(|i| (i * 3, (i % 7) * 5, 4, 2)).collect();
+        let tree = RTree::new(claims.clone());
+
+        for (i, claim) in claims.iter().enumerate() {
+            let brute: Vec<usize> = (0..claims.len())
+                .filter(|&j| spatial::intersects(*claim, claims[j]))
+                .collect();
+            assert_eq!(tree.intersecting(*claim), brute, "claim {}", i);
+        }
+    }
 }
 fn main() {
     let args: Vec<String> = env::args().collect();
+    let (flags, paths): (Vec<String>, Vec<String>) =
+        args.iter().skip(1).cloned().partition(|arg| arg.starts_with("--"));
     let year = "2018".to_string();
     let day = "3".to_string();
 
     let root = env::current_dir().unwrap();
-    let path_str = if args.len() > 1 {
-        args[1].clone()
+    let path_str = if !paths.is_empty() {
+        paths[0].clone()
     } else if root.ends_with(format!("rust_{}_{}", year, day)) {
         format!("../../../Inputs/{}_{}.txt", year, day)
     } else {
@@ -98,7 +144,7 @@ fn main() {
     };
 
 
-    let contents = fs::read_to_string(if args.len() > 1 {path_str} else {RelativePath::new(&path_str).to_path(&root).display().to_string()})
+    let contents = fs::read_to_string(if !paths.is_empty() {path_str} else {RelativePath::new(&path_str).to_path(&root).display().to_string()})
         .expect("Should have been able to read the file");
 
     let part1_timer = Instant::now();
@@ -114,4 +160,47 @@ fn main() {
         part2(contents.clone()),
         part2_timer.elapsed()
     );
-}
\ No newline at end of file
+
+    if flags.is_empty() {
+        return;
+    }
+
+    let claims = parse_claims(&contents);
+    let tree = RTree::new(claims.clone());
+    let scale = flags
+        .iter()
+        .find_map(|flag| flag.strip_prefix("--scale="))
+        .map_or(1, |n| n.parse::<usize>().expect("Should have been a number"));
+    println!();
+    for flag in &flags {
+        match flag.split_once('=') {
+            Some(("--point", point)) => {
+                let nums = parse_nums(point);
+                println!("Claims covering {},{}: {}", nums[0], nums[1], claim_ids(&tree.covering(nums[0], nums[1])));
+            }
+            Some(("--rect", rect)) => {
+                let nums = parse_nums(rect);
+                println!("Claims intersecting {}: {}", rect, claim_ids(&tree.intersecting((nums[0], nums[1], nums[2], nums[3]))));
+            }
+            Some(("--ppm", path)) => {
+                fs::write(path, render::heatmap(&claims, scale).to_ppm()).expect("Should have been able to write the image");
+                println!("Wrote heatmap to {}", path);
+            }
+            Some(("--png", path)) => {
+                fs::write(path, render::heatmap(&claims, scale).to_png()).expect("Should have been able to write the image");
+                println!("Wrote heatmap to {}", path);
+            }
+            Some(("--scale", _)) => {}
+            _ if flag == "--ascii" => println!("{}", render::ascii(&claims)),
+            _ if flag == "--cluster" => {
+                let cluster = tree.largest_overlap_cluster();
+                println!("Largest overlap cluster ({} claims): {}", cluster.len(), claim_ids(&cluster));
+            }
+            _ if flag == "--depth" => {
+                let (depth, (x, y)) = tree.max_depth();
+                println!("Max coverage depth: {} at {},{}", depth, x, y);
+            }
+            _ => panic!("Unknown option {}", flag),
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use crate::spatial::{Rect, RTree};
+
+const EMPTY: [u8; 3] = [20, 20, 30];
+const SINGLE: [u8; 3] = [60, 90, 160];
+const LOW_OVERLAP: [u8; 3] = [240, 200, 60];
+const HIGH_OVERLAP: [u8; 3] = [200, 30, 30];
+const ISOLATED: [u8; 3] = [60, 200, 90];
+const OUTLINE: [u8; 3] = [255, 255, 255];
+
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<[u8; 3]>,
+}
+
+impl Image {
+    fn new(width: usize, height: usize) -> Image {
+        Image {
+            width,
+            height,
+            pixels: vec![EMPTY; width * height],
+        }
+    }
+
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut out = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        out.extend(self.pixels.iter().flatten());
+        out
+    }
+
+    // Uncompressed (stored block) deflate keeps the encoder dependency free
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut raw: Vec<u8> = Vec::with_capacity((self.width * 3 + 1) * self.height);
+        for row in self.pixels.chunks(self.width.max(1)) {
+            raw.push(0);
+            raw.extend(row.iter().flatten());
+        }
+
+        let mut zlib: Vec<u8> = vec![0x78, 0x01];
+        let blocks: Vec<&[u8]> = raw.chunks(u16::MAX as usize).collect();
+        for (i, block) in blocks.iter().enumerate() {
+            zlib.push((i + 1 == blocks.len()) as u8);
+            zlib.extend((block.len() as u16).to_le_bytes());
+            zlib.extend((!(block.len() as u16)).to_le_bytes());
+            zlib.extend(*block);
+        }
+        if blocks.is_empty() {
+            zlib.extend([1, 0, 0, 0xff, 0xff]);
+        }
+        zlib.extend(adler32(&raw).to_be_bytes());
+
+        let mut header: Vec<u8> = Vec::new();
+        header.extend((self.width as u32).to_be_bytes());
+        header.extend((self.height as u32).to_be_bytes());
+        header.extend([8, 2, 0, 0, 0]);
+
+        let mut out: Vec<u8> = vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+        png_chunk(&mut out, b"IHDR", &header);
+        png_chunk(&mut out, b"IDAT", &zlib);
+        png_chunk(&mut out, b"IEND", &[]);
+        out
+    }
+}
+
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend((data.len() as u32).to_be_bytes());
+    out.extend(kind);
+    out.extend(data);
+    out.extend(crc32(kind.iter().chain(data)).to_be_bytes());
+}
+
+fn crc32<'a>(bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    let mut crc = u32::MAX;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in bytes {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+
+    (b << 16) | a
+}
+
+fn coverage(claims: &[Rect]) -> (usize, usize, Vec<usize>) {
+    let width = claims.iter().map(|c| c.0 + c.2).max().unwrap_or(0).max(0) as usize + 1;
+    let height = claims.iter().map(|c| c.1 + c.3).max().unwrap_or(0).max(0) as usize + 1;
+
+    let mut counts = vec![0; width * height];
+    for &(x0, y0, w, h) in claims {
+        for y in y0.max(0)..(y0 + h) {
+            for x in x0.max(0)..(x0 + w) {
+                counts[y as usize * width + x as usize] += 1;
+            }
+        }
+    }
+
+    (width, height, counts)
+}
+
+fn lerp(a: [u8; 3], b: [u8; 3], t: f64) -> [u8; 3] {
+    [0, 1, 2].map(|i| (a[i] as f64 + (b[i] as f64 - a[i] as f64) * t).round() as u8)
+}
+
+// Squares are coloured by how many claims cover them, claims that overlap
+// nothing are filled green and the first of those (the part 2 answer) is
+// outlined. Each square becomes a scale x scale block of pixels.
+pub fn heatmap(claims: &[Rect], scale: usize) -> Image {
+    let (width, height, counts) = coverage(claims);
+    let max_count = counts.iter().cloned().max().unwrap_or(0);
+    let tree = RTree::new(claims.to_vec());
+    let isolated = tree.isolated();
+
+    let mut colours: Vec<[u8; 3]> = counts
+        .iter()
+        .map(|&c| match c {
+            0 => EMPTY,
+            1 => SINGLE,
+            _ if max_count == 2 => LOW_OVERLAP,
+            _ => lerp(LOW_OVERLAP, HIGH_OVERLAP, (c - 2) as f64 / (max_count - 2) as f64),
+        })
+        .collect();
+
+    for (n, &i) in isolated.iter().enumerate() {
+        let (x0, y0, w, h) = claims[i];
+        for y in y0.max(0)..(y0 + h) {
+            for x in x0.max(0)..(x0 + w) {
+                let edge = x == x0 || y == y0 || x == x0 + w - 1 || y == y0 + h - 1;
+                colours[y as usize * width + x as usize] = if n == 0 && edge { OUTLINE } else { ISOLATED };
+            }
+        }
+    }
+
+    let scale = scale.max(1);
+    let mut image = Image::new(width * scale, height * scale);
+    for y in 0..image.height {
+        for x in 0..image.width {
+            image.pixels[y * image.width + x] = colours[(y / scale) * width + x / scale];
+        }
+    }
+
+    image
+}
+
+// Puzzle style drawing: claim IDs where one claim covers a square, X where
+// several do
+pub fn ascii(claims: &[Rect]) -> String {
+    let (width, height, counts) = coverage(claims);
+    let mut grid: Vec<Vec<char>> = vec![vec!['.'; width]; height];
+
+    for (i, &(x0, y0, w, h)) in claims.iter().enumerate() {
+        let id = std::char::from_digit(i as u32 + 1, 36).unwrap_or('#');
+        for y in y0.max(0)..(y0 + h) {
+            for x in x0.max(0)..(x0 + w) {
+                let (x, y) = (x as usize, y as usize);
+                grid[y][x] = if counts[y * width + x] > 1 { 'X' } else { id };
+            }
+        }
+    }
+
+    grid.iter()
+        .map(|row| row.iter().collect::<String>())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
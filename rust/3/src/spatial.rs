@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+// Claims are (x, y, w, h) and cover the half-open squares x..x + w, y..y + h
+pub type Rect = (i64, i64, i64, i64);
+
+const NODE_CAPACITY: usize = 8;
+
+pub fn intersects(a: Rect, b: Rect) -> bool {
+    a.0 < b.0 + b.2 && a.0 + a.2 > b.0 && a.1 < b.1 + b.3 && a.1 + a.3 > b.1
+}
+
+fn bounding_box(rects: &[(Rect, usize)]) -> Rect {
+    let min_x = rects.iter().map(|(r, _)| r.0).min().unwrap();
+    let min_y = rects.iter().map(|(r, _)| r.1).min().unwrap();
+    let max_x = rects.iter().map(|(r, _)| r.0 + r.2).max().unwrap();
+    let max_y = rects.iter().map(|(r, _)| r.1 + r.3).max().unwrap();
+
+    (min_x, min_y, max_x - min_x, max_y - min_y)
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Leaf(Vec<usize>),
+    Inner(Vec<usize>),
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    bbox: Rect,
+    node: Node,
+}
+
+// Static R-tree, bulk loaded with sort-tile-recursive packing. Queries return
+// indices into the rects the tree was built from.
+#[derive(Debug, Clone)]
+pub struct RTree {
+    rects: Vec<Rect>,
+    nodes: Vec<Entry>,
+    root: Option<usize>,
+}
+
+impl RTree {
+    pub fn new(rects: Vec<Rect>) -> RTree {
+        let mut nodes: Vec<Entry> = Vec::new();
+        let mut level: Vec<(Rect, usize)> = rects.iter().cloned().zip(0..).collect();
+        let mut leaves = true;
+
+        while !level.is_empty() && (leaves || level.len() > 1) {
+            level = str_pack(level)
+                .into_iter()
+                .map(|group| {
+                    let bbox = bounding_box(&group);
+                    let ids = group.iter().map(|(_, id)| *id).collect();
+                    nodes.push(Entry {
+                        bbox,
+                        node: if leaves { Node::Leaf(ids) } else { Node::Inner(ids) },
+                    });
+                    (bbox, nodes.len() - 1)
+                })
+                .collect();
+            leaves = false;
+        }
+
+        let root = level.first().map(|(_, id)| *id);
+        RTree { rects, nodes, root }
+    }
+
+    pub fn intersecting(&self, area: Rect) -> Vec<usize> {
+        let mut found: Vec<usize> = Vec::new();
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+
+        while let Some(id) = stack.pop() {
+            let entry = &self.nodes[id];
+            if !intersects(entry.bbox, area) {
+                continue;
+            }
+
+            match &entry.node {
+                Node::Leaf(items) => found.extend(
+                    items
+                        .iter()
+                        .filter(|&&i| intersects(self.rects[i], area)),
+                ),
+                Node::Inner(children) => stack.extend(children),
+            }
+        }
+
+        found.sort();
+        found
+    }
+
+    pub fn covering(&self, x: i64, y: i64) -> Vec<usize> {
+        self.intersecting((x, y, 1, 1))
+    }
+
+    // Claims that overlap no other claim
+    pub fn isolated(&self) -> Vec<usize> {
+        (0..self.rects.len())
+            .filter(|&i| self.intersecting(self.rects[i]).iter().all(|&j| j == i))
+            .collect()
+    }
+
+    // Claims are connected when they overlap; ties go to the cluster with the
+    // lowest claim in it
+    pub fn largest_overlap_cluster(&self) -> Vec<usize> {
+        let mut parent: Vec<usize> = (0..self.rects.len()).collect();
+        for (i, rect) in self.rects.iter().enumerate() {
+            for j in self.intersecting(*rect) {
+                let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+                if a != b {
+                    parent[a.max(b)] = a.min(b);
+                }
+            }
+        }
+
+        let mut clusters: HashMap<usize, Vec<usize>> = HashMap::new();
+        for i in 0..self.rects.len() {
+            let root = find(&mut parent, i);
+            clusters.entry(root).or_default().push(i);
+        }
+
+        clusters
+            .into_values()
+            .max_by_key(|c| (c.len(), std::cmp::Reverse(c[0])))
+            .unwrap_or_default()
+    }
+
+    // Deepest coverage and the top-left square where it's reached
+    pub fn max_depth(&self) -> (usize, (i64, i64)) {
+        let mut xs: Vec<i64> = self.rects.iter().flat_map(|r| [r.0, r.0 + r.2]).collect();
+        let mut ys: Vec<i64> = self.rects.iter().flat_map(|r| [r.1, r.1 + r.3]).collect();
+        xs.sort();
+        xs.dedup();
+        ys.sort();
+        ys.dedup();
+
+        let mut diff: Vec<Vec<i64>> = vec![vec![0; xs.len()]; ys.len()];
+        for r in self.rects.iter().filter(|r| r.2 > 0 && r.3 > 0) {
+            let x0 = xs.binary_search(&r.0).unwrap();
+            let x1 = xs.binary_search(&(r.0 + r.2)).unwrap();
+            let y0 = ys.binary_search(&r.1).unwrap();
+            let y1 = ys.binary_search(&(r.1 + r.3)).unwrap();
+
+            diff[y0][x0] += 1;
+            diff[y0][x1] -= 1;
+            diff[y1][x0] -= 1;
+            diff[y1][x1] += 1;
+        }
+
+        let mut best: (usize, (i64, i64)) = (0, (0, 0));
+        for y in 0..ys.len() {
+            for x in 0..xs.len() {
+                if y > 0 {
+                    diff[y][x] += diff[y - 1][x];
+                }
+                if x > 0 {
+                    diff[y][x] += diff[y][x - 1];
+                }
+                if y > 0 && x > 0 {
+                    diff[y][x] -= diff[y - 1][x - 1];
+                }
+
+                if diff[y][x] as usize > best.0 {
+                    best = (diff[y][x] as usize, (xs[x], ys[y]));
+                }
+            }
+        }
+
+        best
+    }
+}
+
+fn str_pack(mut items: Vec<(Rect, usize)>) -> Vec<Vec<(Rect, usize)>> {
+    let leaves = items.len().div_ceil(NODE_CAPACITY);
+    let strip_len = (leaves as f64).sqrt().ceil() as usize * NODE_CAPACITY;
+
+    items.sort_by_key(|(r, _)| 2 * r.0 + r.2);
+    items
+        .chunks_mut(strip_len)
+        .flat_map(|strip| {
+            strip.sort_by_key(|(r, _)| 2 * r.1 + r.3);
+            strip
+                .chunks(NODE_CAPACITY)
+                .map(|group| group.to_vec())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn find(parent: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parent[root] != root {
+        root = parent[root];
+    }
+
+    let mut i = i;
+    while parent[i] != root {
+        let next = parent[i];
+        parent[i] = root;
+        i = next;
+    }
+
+    root
+}
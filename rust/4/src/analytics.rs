@@ -0,0 +1,163 @@
+use crate::Shift;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+// Scores a guard; the highest scoring guard is picked, lowest ID on ties
+pub type Strategy = fn(&Guard) -> i64;
+
+pub fn most_total_sleep(guard: &Guard) -> i64 {
+    guard.total_sleep()
+}
+
+pub fn most_frequent_minute(guard: &Guard) -> i64 {
+    guard.most_asleep().map_or(0, |(_, count)| count)
+}
+
+pub fn guards(shifts: &[Shift]) -> Vec<Guard> {
+    let mut guards: HashMap<i64, Guard> = HashMap::new();
+    for shift in shifts {
+        let guard = guards
+            .entry(shift.guard)
+            .or_insert_with(|| Guard::new(shift.guard));
+        guard.sleep_times.extend(&shift.naps);
+        guard.shifts += 1;
+    }
+
+    let mut guards: Vec<Guard> = guards.into_values().collect();
+    guards.sort_by_key(|g| g.id);
+    guards
+}
+
+// The chosen guard and their sleepiest minute. Guards who never slept in
+// the midnight hour can't be chosen since they have no such minute.
+pub fn evaluate(guards: &[Guard], strategy: Strategy) -> Option<(i64, i64)> {
+    guards
+        .iter()
+        .filter_map(|g| Some((g, g.most_asleep()?.0[0])))
+        .max_by_key(|(g, _)| (strategy(g), Reverse(g.id)))
+        .map(|(g, minute)| (g.id, minute))
+}
+
+#[derive(Debug, Ord, PartialOrd, PartialEq, Eq, Hash, Clone)]
+pub struct Guard {
+    pub id: i64,
+    pub shifts: usize,
+    sleep_times: Vec<(i64, i64)>,
+}
+
+impl Guard {
+    fn new(id: i64) -> Guard {
+        Guard {
+            id,
+            shifts: 0,
+            sleep_times: Vec::new(),
+        }
+    }
+
+    pub fn total_sleep(&self) -> i64 {
+        self.sleep_times.iter().map(|x| x.1 - x.0).sum()
+    }
+
+    // Only the midnight hour counts towards the minute tallies
+    pub fn minute_counts(&self) -> [i64; 60] {
+        let mut minutes = [0; 60];
+        for (start, end) in &self.sleep_times {
+            for i in *start.max(&0)..*end.min(&60) {
+                minutes[i as usize] += 1;
+            }
+        }
+
+        minutes
+    }
+
+    // Every minute tied for most asleep, in order, and how often that was
+    pub fn most_asleep(&self) -> Option<(Vec<i64>, i64)> {
+        let minutes = self.minute_counts();
+        let count = *minutes.iter().max().unwrap();
+        if count == 0 {
+            return None;
+        }
+
+        Some((
+            (0..60).filter(|&i| minutes[i as usize] == count).collect(),
+            count,
+        ))
+    }
+}
+
+pub fn stats_table(guards: &[Guard]) -> String {
+    let mut table = String::from("Guard   Shifts  Asleep  Minute  Times");
+    for guard in guards {
+        let (minutes, count) = guard.most_asleep().unwrap_or_default();
+        let minutes = minutes
+            .iter()
+            .map(|m| format!("{:02}", m))
+            .collect::<Vec<String>>()
+            .join("/");
+
+        table += &format!(
+            "\n{:<8}{:<8}{:<8}{:<8}{}",
+            format!("#{}", guard.id),
+            guard.shifts,
+            guard.total_sleep(),
+            if minutes.is_empty() { "-".to_string() } else { minutes },
+            count
+        );
+    }
+
+    table
+}
+
+pub fn heatmap_csv(guards: &[Guard]) -> String {
+    let mut csv = String::from("guard");
+    for minute in 0..60 {
+        csv += &format!(",{}", minute);
+    }
+
+    for guard in guards {
+        csv += &format!("\n{}", guard.id);
+        for count in guard.minute_counts() {
+            csv += &format!(",{}", count);
+        }
+    }
+
+    csv + "\n"
+}
+
+// The puzzle's Date / ID / Minute chart, one row per shift
+pub fn chart(shifts: &[Shift]) -> String {
+    let id_width = shifts
+        .iter()
+        .map(|s| s.guard.to_string().len() + 1)
+        .max()
+        .unwrap_or(2)
+        .max(2);
+    let indent = " ".repeat(7 + id_width + 2);
+
+    let mut chart = format!("{:<7}{:<w$}Minute", "Date", "ID", w = id_width + 2);
+    chart += &format!("\n{}{}", indent, (0..60).map(|m| (b'0' + m / 10) as char).collect::<String>());
+    chart += &format!("\n{}{}", indent, (0..60).map(|m| (b'0' + m % 10) as char).collect::<String>());
+
+    for shift in shifts {
+        let minutes: String = (0..60)
+            .map(|m| {
+                if shift.naps.iter().any(|(start, end)| (*start..*end).contains(&m)) {
+                    '#'
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+
+        chart += &format!(
+            "\n{:02}-{:02}  {:<w$}  {}",
+            shift.date.month,
+            shift.date.day,
+            format!("#{}", shift.guard),
+            minutes,
+            w = id_width
+        );
+    }
+
+    chart
+}
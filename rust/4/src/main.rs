@@ -2,154 +2,241 @@ use relative_path::RelativePath;
 use std::env;
 use std::fs;
 use std::time::Instant;
-use std::collections::HashMap;
+use std::fmt;
 
-fn part1(contents: String) -> i64 {
-    let mut lines = contents.lines().collect::<Vec<&str>>();
-    lines.sort_by(|a, b| {
-        let date_a = DateTime::new(a);
-        let date_b = DateTime::new(b);
-        
-        return date_a.cmp(&date_b);
-    });
-
-    let mut guards: HashMap<i64, Guard> = HashMap::new();
-    let mut current_guard: i64 = 0;
-    for line in lines {
-        let date = DateTime::new(line);
-        if line.contains("Guard") {
-            let id = line.split_whitespace().collect::<Vec<&str>>()[3].replace("#", "").parse::<i64>().unwrap();
-            if !guards.contains_key(&id) {
-                guards.insert(id, Guard::new(id));
-            }
+mod analytics;
 
-            current_guard = id;
-        } else if line.contains("falls asleep") {
-            let start = DateTime::new(line);
-            let end = DateTime::new(line);
-            guards.get_mut(&current_guard).unwrap().sleep_times.push((start, end));
-        } else if line.contains("wakes up") {
-            let end = DateTime::new(line);
-            guards.get_mut(&current_guard).unwrap().sleep_times.last_mut().unwrap().1 = end;
-        }
-    }
+use analytics::{evaluate, guards, most_frequent_minute, most_total_sleep};
 
+fn part1(contents: String) -> i64 {
+    let shifts = parse_shifts(&contents).expect("Should have been a valid guard log");
+    let (guard, minute) = evaluate(&guards(&shifts), most_total_sleep).expect("Some guard should have slept");
 
-    let max_guard = guards.iter().max_by_key(|x| x.1.total_sleep()).unwrap().1;
-    
-    return max_guard.id * max_guard.most_asleep() as i64;
+    guard * minute
 }
 
 fn part2(contents: String) -> i64 {
-    let mut lines = contents.lines().collect::<Vec<&str>>();
-    lines.sort_by(|a, b| {
-        let date_a = DateTime::new(a);
-        let date_b = DateTime::new(b);
-        
-        return date_a.cmp(&date_b);
-    });
-
-    let mut guards: HashMap<i64, Guard> = HashMap::new();
-    let mut current_guard: i64 = 0;
-    for line in lines {
-        let date = DateTime::new(line);
-        if line.contains("Guard") {
-            let id = line.split_whitespace().collect::<Vec<&str>>()[3].replace("#", "").parse::<i64>().unwrap();
-            if !guards.contains_key(&id) {
-                guards.insert(id, Guard::new(id));
-            }
+    let shifts = parse_shifts(&contents).expect("Should have been a valid guard log");
+    let (guard, minute) = evaluate(&guards(&shifts), most_frequent_minute).expect("Some guard should have slept");
 
-            current_guard = id;
-        } else if line.contains("falls asleep") {
-            let start = DateTime::new(line);
-            let end = DateTime::new(line);
-            guards.get_mut(&current_guard).unwrap().sleep_times.push((start, end));
-        } else if line.contains("wakes up") {
-            let end = DateTime::new(line);
-            guards.get_mut(&current_guard).unwrap().sleep_times.last_mut().unwrap().1 = end;
-        }
+    guard * minute
+}
+
+#[derive(Debug, Ord, PartialOrd, PartialEq, Eq, Hash, Clone, Copy)]
+struct Date {
+    year: i64,
+    month: i64,
+    day: i64,
+}
+
+impl Date {
+    // Days since 1970-01-01 in the proleptic Gregorian calendar
+    fn days(&self) -> i64 {
+        let y = if self.month <= 2 { self.year - 1 } else { self.year };
+        let era = y.div_euclid(400);
+        let yoe = y - era * 400;
+        let doy = (153 * ((self.month + 9) % 12) + 2) / 5 + self.day - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+        era * 146097 + doe - 719468
     }
 
-    let mut minute_counts: HashMap<i64, HashMap<i64, i64>> = HashMap::new();
-    let mut most_minute = 0;
-    let mut most_guard = 0;
-    let mut most_times = 0;
-
-    for guard in guards.values() {
-        for (start, end) in &guard.sleep_times {
-            for i in start.minute..end.minute {
-                *minute_counts.entry(i).or_insert(HashMap::new()).entry(guard.id).or_insert(0) += 1;
-                if *minute_counts.get(&i).unwrap().get(&guard.id).unwrap() > most_times {
-                    most_times = *minute_counts.get(&i).unwrap().get(&guard.id).unwrap();
-                    most_minute = i;
-                    most_guard = guard.id;
-                }
-            }
+    fn from_days(days: i64) -> Date {
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+
+        Date {
+            year: yoe + era * 400 + if month <= 2 { 1 } else { 0 },
+            month,
+            day: doy - (153 * mp + 2) / 5 + 1,
         }
     }
-    
-    return most_minute * most_guard;
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
 }
 
 #[derive(Debug, Ord, PartialOrd, PartialEq, Eq, Hash, Clone, Copy)]
 struct DateTime {
-    year: i64,
-    month: i64,
-    day: i64,
+    date: Date,
     hour: i64,
     minute: i64,
 }
 
 impl DateTime {
-    fn new(date_str: &str) -> DateTime {
-        let datestr = date_str.replace("[", "").replace("]", "");
-        let date = datestr.split_whitespace().collect::<Vec<&str>>()[0];
-        let time = datestr.split_whitespace().collect::<Vec<&str>>()[1];
+    // Parses "YYYY-MM-DD HH:MM", rejecting dates and times that don't exist
+    fn parse(stamp: &str) -> Option<DateTime> {
+        let (date, time) = stamp.trim().split_once(' ')?;
+        let date_parts = date.split('-').map(|p| p.parse::<i64>().ok()).collect::<Option<Vec<i64>>>()?;
+        let time_parts = time.trim().split(':').map(|p| p.parse::<i64>().ok()).collect::<Option<Vec<i64>>>()?;
+        if date_parts.len() != 3 || time_parts.len() != 2 {
+            return None;
+        }
 
-        let year = date.split("-").collect::<Vec<&str>>()[0].parse::<i64>().unwrap();
-        let month = date.split("-").collect::<Vec<&str>>()[1].parse::<i64>().unwrap();
-        let day = date.split("-").collect::<Vec<&str>>()[2].parse::<i64>().unwrap();
+        let date = Date {
+            year: date_parts[0],
+            month: date_parts[1],
+            day: date_parts[2],
+        };
+        let valid_date = (1..=12).contains(&date.month) && Date::from_days(date.days()) == date;
+        if !valid_date || !(0..24).contains(&time_parts[0]) || !(0..60).contains(&time_parts[1]) {
+            return None;
+        }
 
-        let hour = time.split(":").collect::<Vec<&str>>()[0].parse::<i64>().unwrap();
-        let minute = time.split(":").collect::<Vec<&str>>()[1].parse::<i64>().unwrap();
+        Some(DateTime {
+            date,
+            hour: time_parts[0],
+            minute: time_parts[1],
+        })
+    }
 
-        DateTime {
-            year,
-            month,
-            day,
-            hour,
-            minute,
+    fn timestamp(&self) -> i64 {
+        self.date.days() * 1440 + self.hour * 60 + self.minute
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Event {
+    Begin(i64),
+    Sleep,
+    Wake,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum LogError {
+    Malformed { line: usize, text: String },
+    NoGuard { line: usize },
+    DoubleSleep { line: usize, guard: i64 },
+    UnmatchedWake { line: usize, guard: i64 },
+    UnfinishedSleep { guard: i64, date: Date },
+}
+
+impl fmt::Display for LogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LogError::Malformed { line, text } => write!(f, "line {}: can't parse \"{}\"", line, text),
+            LogError::NoGuard { line } => write!(f, "line {}: no guard is on shift", line),
+            LogError::DoubleSleep { line, guard } => write!(f, "line {}: guard #{} falls asleep while already asleep", line, guard),
+            LogError::UnmatchedWake { line, guard } => write!(f, "line {}: guard #{} wakes up without falling asleep", line, guard),
+            LogError::UnfinishedSleep { guard, date } => write!(f, "guard #{} is still asleep at the end of the {} shift", guard, date),
         }
     }
 }
 
-#[derive(Debug, Ord, PartialOrd, PartialEq, Eq, Hash, Clone)]
-struct Guard {
-    id: i64,
-    sleep_times: Vec<(DateTime, DateTime)>,
+fn parse_record(line: &str) -> Option<(DateTime, Event)> {
+    let (stamp, text) = line.trim().strip_prefix('[')?.split_once(']')?;
+    let event = match text.trim() {
+        "falls asleep" => Event::Sleep,
+        "wakes up" => Event::Wake,
+        begin => Event::Begin(
+            begin
+                .strip_prefix("Guard #")?
+                .strip_suffix(" begins shift")?
+                .parse()
+                .ok()?,
+        ),
+    };
+
+    Some((DateTime::parse(stamp)?, event))
+}
+
+// A guard's time on duty. Shifts that begin before midnight count towards the
+// next day, and naps are [start, end) minutes relative to that day's midnight.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Shift {
+    guard: i64,
+    date: Date,
+    naps: Vec<(i64, i64)>,
 }
 
-impl Guard {
-    fn new(id: i64) -> Guard {
-        Guard {
-            id,
-            sleep_times: Vec::new(),
+impl Shift {
+    fn begin(guard: i64, start: DateTime) -> Shift {
+        let date = if start.hour >= 12 {
+            Date::from_days(start.date.days() + 1)
+        } else {
+            start.date
+        };
+
+        Shift {
+            guard,
+            date,
+            naps: Vec::new(),
         }
     }
 
-    fn total_sleep(&self) -> i64 {
-        return self.sleep_times.iter().map(|x| x.1.minute - x.0.minute).sum();
+    fn minute(&self, time: DateTime) -> i64 {
+        time.timestamp() - self.date.days() * 1440
+    }
+}
+
+// Builds shifts from the log in any order, collecting every problem found
+// rather than stopping at the first
+fn parse_shifts(contents: &str) -> Result<Vec<Shift>, Vec<LogError>> {
+    let mut errors: Vec<LogError> = Vec::new();
+    let mut records: Vec<(DateTime, Event, usize)> = Vec::new();
+    for (i, line) in contents.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        match parse_record(line) {
+            Some((time, event)) => records.push((time, event, i + 1)),
+            None => errors.push(LogError::Malformed {
+                line: i + 1,
+                text: line.to_string(),
+            }),
+        }
     }
+    records.sort_by_key(|(time, _, line)| (*time, *line));
 
-    fn most_asleep(&self) -> i64 {
-        let mut minutes: HashMap<i64, i64> = HashMap::new();
-        for (start, end) in &self.sleep_times {
-            for i in start.minute..end.minute {
-                *minutes.entry(i).or_insert(0) += 1;
+    let mut shifts: Vec<Shift> = Vec::new();
+    let mut asleep: Option<DateTime> = None;
+    for (time, event, line) in records {
+        if let Event::Begin(guard) = event {
+            if let (Some(shift), Some(_)) = (shifts.last(), asleep.take()) {
+                errors.push(LogError::UnfinishedSleep {
+                    guard: shift.guard,
+                    date: shift.date,
+                });
             }
+
+            shifts.push(Shift::begin(guard, time));
+            continue;
+        }
+
+        let Some(shift) = shifts.last_mut() else {
+            errors.push(LogError::NoGuard { line });
+            continue;
+        };
+
+        match (event, asleep) {
+            (Event::Sleep, None) => asleep = Some(time),
+            (Event::Sleep, Some(_)) => errors.push(LogError::DoubleSleep { line, guard: shift.guard }),
+            (Event::Wake, Some(start)) => {
+                let nap = (shift.minute(start), shift.minute(time));
+                shift.naps.push(nap);
+                asleep = None;
+            }
+            (Event::Wake, None) => errors.push(LogError::UnmatchedWake { line, guard: shift.guard }),
+            (Event::Begin(_), _) => unreachable!(),
         }
+    }
 
-        return *minutes.iter().max_by_key(|x| x.1).unwrap().0;
+    if let (Some(shift), Some(_)) = (shifts.last(), asleep) {
+        errors.push(LogError::UnfinishedSleep {
+            guard: shift.guard,
+            date: shift.date,
+        });
+    }
+
+    if errors.is_empty() {
+        Ok(shifts)
+    } else {
+        Err(errors)
     }
 }
 
@@ -172,15 +259,142 @@ mod tests {
 
         assert_eq!(part2(contents), 4455);
     }
+
+    const EXAMPLE: &str = "[1518-11-01 00:00] Guard #10 begins shift
+[1518-11-01 00:05] falls asleep
+[1518-11-01 00:25] wakes up
+[1518-11-01 00:30] falls asleep
+[1518-11-01 00:55] wakes up
+[1518-11-01 23:58] Guard #99 begins shift
+[1518-11-02 00:40] falls asleep
+[1518-11-02 00:50] wakes up
+[1518-11-03 00:05] Guard #10 begins shift
+[1518-11-03 00:24] falls asleep
+[1518-11-03 00:29] wakes up
+[1518-11-04 00:02] Guard #99 begins shift
+[1518-11-04 00:36] falls asleep
+[1518-11-04 00:46] wakes up
+[1518-11-05 00:03] Guard #99 begins shift
+[1518-11-05 00:45] falls asleep
+[1518-11-05 00:55] wakes up";
+
+    #[test]
+    fn shifts_test() {
+        let mut lines: Vec<&str> = EXAMPLE.lines().collect();
+        lines.reverse();
+        let shifts = parse_shifts(&lines.join("\n")).unwrap();
+
+        assert_eq!(shifts.len(), 5);
+        assert_eq!(shifts[1].guard, 99);
+        assert_eq!(shifts[1].date, Date { year: 1518, month: 11, day: 2 });
+        assert_eq!(shifts[0].naps, vec![(5, 25), (30, 55)]);
+        assert_eq!(part1(EXAMPLE.to_string()), 240);
+        assert_eq!(part2(EXAMPLE.to_string()), 4455);
+    }
+
+    #[test]
+    fn shift_across_midnight_test() {
+        let log = "[1518-02-28 23:50] Guard #7 begins shift
+[1518-02-28 23:55] falls asleep
+[1518-03-01 00:05] wakes up
+[1518-03-01 00:50] falls asleep
+[1518-03-01 01:10] wakes up";
+        let shifts = parse_shifts(log).unwrap();
+
+        assert_eq!(shifts[0].date, Date { year: 1518, month: 3, day: 1 });
+        assert_eq!(shifts[0].naps, vec![(-5, 5), (50, 70)]);
+        assert_eq!(guards(&shifts)[0].total_sleep(), 30);
+        assert_eq!(guards(&shifts)[0].minute_counts().iter().sum::<i64>(), 15);
+    }
+
+    #[test]
+    fn asleep_before_midnight_test() {
+        // Guard 8 sleeps the longest, but all of it before midnight
+        let log = "[1518-03-01 23:00] Guard #8 begins shift
+[1518-03-01 23:05] falls asleep
+[1518-03-01 23:55] wakes up
+[1518-03-02 00:00] Guard #9 begins shift
+[1518-03-02 00:20] falls asleep
+[1518-03-02 00:25] wakes up";
+        let guards = guards(&parse_shifts(log).unwrap());
+
+        assert_eq!(guards[0].total_sleep(), 50);
+        assert_eq!(guards[0].most_asleep(), None);
+        assert_eq!(evaluate(&guards, most_total_sleep), Some((9, 20)));
+        assert_eq!(part1(log.to_string()), 9 * 20);
+    }
+
+    #[test]
+    fn invalid_log_test() {
+        let log = "[1518-11-01 00:01] falls asleep
+[1518-11-01 00:02] Guard #10 begins shift
+[1518-11-01 00:03] wakes up
+[1518-11-01 00:04] falls asleep
+[1518-11-01 00:05] falls asleep
+[1518-11-02 00:00] Guard #11 begins shift
+[1518-02-30 00:00] wakes up";
+
+        assert_eq!(
+            parse_shifts(log),
+            Err(vec![
+                LogError::Malformed { line: 7, text: "[1518-02-30 00:00] wakes up".to_string() },
+                LogError::NoGuard { line: 1 },
+                LogError::UnmatchedWake { line: 3, guard: 10 },
+                LogError::DoubleSleep { line: 5, guard: 10 },
+                LogError::UnfinishedSleep { guard: 10, date: Date { year: 1518, month: 11, day: 1 } },
+            ])
+        );
+    }
+
+    #[test]
+    fn analytics_test() {
+        let shifts = parse_shifts(&format!("{}\n[1518-11-06 00:00] Guard #5 begins shift", EXAMPLE)).unwrap();
+        let guards = guards(&shifts);
+
+        assert_eq!(guards.iter().map(|g| g.id).collect::<Vec<i64>>(), vec![5, 10, 99]);
+        assert_eq!(guards[0].most_asleep(), None);
+        assert_eq!(guards[1].most_asleep(), Some((vec![24], 2)));
+        assert_eq!(guards[2].most_asleep(), Some((vec![45], 3)));
+        assert_eq!(evaluate(&guards, most_total_sleep), Some((10, 24)));
+        assert_eq!(evaluate(&guards, |g| -g.total_sleep()), Some((99, 45)));
+
+        let csv = analytics::heatmap_csv(&guards);
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.lines().nth(1).unwrap().starts_with("5,0,0"));
+
+        let chart = analytics::chart(&shifts);
+        assert_eq!(
+            chart.lines().take(4).collect::<Vec<&str>>(),
+            vec![
+                "Date   ID   Minute",
+                "            000000000011111111112222222222333333333344444444445555555555",
+                "            012345678901234567890123456789012345678901234567890123456789",
+                "11-01  #10  .....####################.....#########################.....",
+            ]
+        );
+    }
+
+    #[test]
+    fn tied_minutes_test() {
+        let log = "[1518-11-01 00:00] Guard #3 begins shift
+[1518-11-01 00:10] falls asleep
+[1518-11-01 00:12] wakes up";
+        let guards = guards(&parse_shifts(log).unwrap());
+
+        assert_eq!(guards[0].most_asleep(), Some((vec![10, 11], 1)));
+        assert_eq!(part1(log.to_string()), 30);
+    }
 }
 fn main() {
     let args: Vec<String> = env::args().collect();
+    let (flags, paths): (Vec<String>, Vec<String>) =
+        args.iter().skip(1).cloned().partition(|arg| arg.starts_with("--"));
     let year = "2018".to_string();
     let day = "4".to_string();
 
     let root = env::current_dir().unwrap();
-    let path_str = if args.len() > 1 {
-        args[1].clone()
+    let path_str = if !paths.is_empty() {
+        paths[0].clone()
     } else if root.ends_with(format!("rust_{}_{}", year, day)) {
         format!("../../../Inputs/{}_{}.txt", year, day)
     } else {
@@ -188,9 +402,20 @@ fn main() {
     };
 
 
-    let contents = fs::read_to_string(if args.len() > 1 {path_str} else {RelativePath::new(&path_str).to_path(&root).display().to_string()})
+    let contents = fs::read_to_string(if !paths.is_empty() {path_str} else {RelativePath::new(&path_str).to_path(&root).display().to_string()})
         .expect("Should have been able to read the file");
 
+    let shifts = match parse_shifts(&contents) {
+        Ok(shifts) => shifts,
+        Err(errors) => {
+            println!("\nInvalid guard log:");
+            for error in errors {
+                println!("{}", error);
+            }
+            return;
+        }
+    };
+
     let part1_timer = Instant::now();
     println!(
         "\nPart 1:\nGuard ID * Minute: {}\nRan in {:.5?}",
@@ -204,4 +429,17 @@ fn main() {
         part2(contents.clone()),
         part2_timer.elapsed()
     );
-}
\ No newline at end of file
+
+    for flag in &flags {
+        println!();
+        match flag.split_once('=') {
+            Some(("--csv", path)) => {
+                fs::write(path, analytics::heatmap_csv(&guards(&shifts))).expect("Should have been able to write the file");
+                println!("Wrote minute heatmap to {}", path);
+            }
+            _ if flag == "--stats" => println!("{}", analytics::stats_table(&guards(&shifts))),
+            _ if flag == "--chart" => println!("{}", analytics::chart(&shifts)),
+            _ => panic!("Unknown option {}", flag),
+        }
+    }
+}
@@ -1,47 +1,19 @@
 use relative_path::RelativePath;
-use std::collections::HashSet;
 use std::env;
 use std::fs;
 use std::time::Instant;
 
-fn part1(contents: String) -> i64 {
-    return polymer_len(contents);
-}
+mod polymer;
 
-fn part2(contents: String) -> i64 {
-    let char_set = contents
-        .chars()
-        .map(|c| c.to_ascii_lowercase())
-        .collect::<HashSet<char>>();
-    let mut min_len = std::i64::MAX;
-    for c in char_set {
-        let len = polymer_len(contents.replace(c, "").replace(c.to_ascii_uppercase(), ""));
-        if len < min_len {
-            min_len = len;
-        }
-    }
+use polymer::Rules;
 
-    return min_len;
+fn part1(contents: String) -> i64 {
+    polymer::reduce(contents.trim().as_bytes(), &Rules::default(), None).len() as i64
 }
 
-fn polymer_len(contents: String) -> i64 {
-    let mut stack = Vec::new();
-    for c in contents.chars() {
-        if stack.len() == 0 {
-            stack.push(c);
-        } else {
-            let last = stack.pop().unwrap();
-            if last.to_ascii_lowercase() == c.to_ascii_lowercase() && last != c {
-                continue;
-
-            } else {
-                stack.push(last);
-                stack.push(c);
-            }
-        }
-    }
-
-    return stack.len() as i64;
+fn part2(contents: String) -> i64 {
+    let reduced = polymer::reduce(contents.trim().as_bytes(), &Rules::default(), None);
+    polymer::best_removal(&reduced).map_or(reduced.len(), |(_, len)| len) as i64
 }
 
 #[cfg(test)]
@@ -63,15 +35,57 @@ mod tests {
 
         assert_eq!(part2(contents), 4);
     }
+
+    #[test]
+    fn reduce_test() {
+        let mut trace = Vec::new();
+        let reduced = polymer::reduce(b"dabAcCaCBAcCcaDA", &Rules::default(), Some(&mut trace));
+
+        assert_eq!(reduced, b"dabCBAcaDA".to_vec());
+        assert_eq!(
+            trace,
+            vec![
+                polymer::Reaction { left: 4, right: 5, units: (b'c', b'C') },
+                polymer::Reaction { left: 3, right: 6, units: (b'A', b'a') },
+                polymer::Reaction { left: 10, right: 11, units: (b'c', b'C') },
+            ]
+        );
+        assert_eq!(polymer::reduce(b"aA", &Rules::default(), None), Vec::<u8>::new());
+        assert_eq!(polymer::reduce(b"aabAAB", &Rules::default(), None), b"aabAAB".to_vec());
+    }
+
+    #[test]
+    fn best_removal_test() {
+        let reduced = polymer::reduce(b"dabAcCaCBAcCcaDA", &Rules::default(), None);
+
+        assert_eq!(polymer::best_removal(&reduced), Some((b'c', 4)));
+        assert_eq!(part2("dabAcCaCBAcCcaDA\n".to_string()), 4);
+    }
+
+    #[test]
+    fn rules_test() {
+        let brackets = Rules::parse("(>),[>],{>}").unwrap();
+
+        assert_eq!(polymer::reduce(b"([]{()})", &brackets, None), Vec::<u8>::new());
+        assert_eq!(polymer::reduce(b"(]", &brackets, None), b"(]".to_vec());
+        assert_eq!(polymer::reduce(b")(", &brackets, None), b")(".to_vec());
+
+        let pairs = Rules::parse("ab,case").unwrap();
+        assert_eq!(polymer::reduce(b"xbaXx", &pairs, None), b"x".to_vec());
+        assert_eq!(Rules::parse("case"), Some(Rules::default()));
+        assert_eq!(Rules::parse("abc"), None);
+    }
 }
 fn main() {
     let args: Vec<String> = env::args().collect();
+    let (flags, paths): (Vec<String>, Vec<String>) =
+        args.iter().skip(1).cloned().partition(|arg| arg.starts_with("--"));
     let year = "2018".to_string();
     let day = "5".to_string();
 
     let root = env::current_dir().unwrap();
-    let path_str = if args.len() > 1 {
-        args[1].clone()
+    let path_str = if !paths.is_empty() {
+        paths[0].clone()
     } else if root.ends_with(format!("rust_{}_{}", year, day)) {
         format!("../../../Inputs/{}_{}.txt", year, day)
     } else {
@@ -79,7 +93,7 @@ fn main() {
     };
 
 
-    let contents = fs::read_to_string(if args.len() > 1 {path_str} else {RelativePath::new(&path_str).to_path(&root).display().to_string()})
+    let contents = fs::read_to_string(if !paths.is_empty() {path_str} else {RelativePath::new(&path_str).to_path(&root).display().to_string()})
         .expect("Should have been able to read the file");
 
     let part1_timer = Instant::now();
@@ -95,4 +109,32 @@ fn main() {
         part2(contents.clone().lines().next().unwrap().to_string()),
         part2_timer.elapsed()
     );
-}
\ No newline at end of file
+
+    let polymer = contents.lines().next().unwrap().as_bytes();
+    let rules = flags
+        .iter()
+        .find_map(|flag| flag.strip_prefix("--rules="))
+        .map_or(Rules::default(), |spec| Rules::parse(spec).expect("Should have been valid rules"));
+    for flag in &flags {
+        println!();
+        match flag.as_str() {
+            "--reduced" => println!("Reduced polymer: {}", String::from_utf8_lossy(&polymer::reduce(polymer, &rules, None))),
+            "--trace" => {
+                let mut trace = Vec::new();
+                polymer::reduce(polymer, &rules, Some(&mut trace));
+                for (step, reaction) in trace.iter().enumerate() {
+                    println!(
+                        "{}: {}{} at {} and {}",
+                        step + 1,
+                        reaction.units.0 as char,
+                        reaction.units.1 as char,
+                        reaction.left,
+                        reaction.right
+                    );
+                }
+            }
+            _ if flag.starts_with("--rules=") => println!("Length with custom rules: {}", polymer::reduce(polymer, &rules, None).len()),
+            _ => panic!("Unknown option {}", flag),
+        }
+    }
+}
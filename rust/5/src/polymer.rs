@@ -0,0 +1,126 @@
+use std::thread;
+
+// Two units that annihilated: their positions in the original polymer and
+// the units themselves
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Reaction {
+    pub left: usize,
+    pub right: usize,
+    pub units: (u8, u8),
+}
+
+// Which ordered pairs of adjacent units annihilate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    table: Vec<bool>,
+}
+
+impl Rules {
+    pub fn none() -> Rules {
+        Rules {
+            table: vec![false; 256 * 256],
+        }
+    }
+
+    // Comma separated rules: "ab" annihilates a and b in either order, "a>b"
+    // only when a comes first, and "case" adds the puzzle's polarity rule
+    pub fn parse(spec: &str) -> Option<Rules> {
+        let mut rules = Rules::none();
+        for rule in spec.split(',').filter(|r| !r.is_empty()) {
+            match rule.as_bytes() {
+                b"case" => rules = rules.with_case_pairs(),
+                [a, b] => rules = rules.annihilate(*a, *b),
+                [a, b'>', b] => rules = rules.directional(*a, *b),
+                _ => return None,
+            }
+        }
+
+        Some(rules)
+    }
+
+    pub fn annihilate(self, a: u8, b: u8) -> Rules {
+        self.directional(a, b).directional(b, a)
+    }
+
+    pub fn directional(mut self, left: u8, right: u8) -> Rules {
+        self.table[left as usize * 256 + right as usize] = true;
+        self
+    }
+
+    pub fn with_case_pairs(self) -> Rules {
+        (b'a'..=b'z').fold(self, |rules, c| rules.annihilate(c, c.to_ascii_uppercase()))
+    }
+
+    pub fn reacts(&self, left: u8, right: u8) -> bool {
+        self.table[left as usize * 256 + right as usize]
+    }
+}
+
+// Same letter, opposite case
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules::none().with_case_pairs()
+    }
+}
+
+// Single pass stack reduction. Recording the trace keeps a second stack of
+// original positions, so it's only done when asked for.
+pub fn reduce(polymer: &[u8], rules: &Rules, mut trace: Option<&mut Vec<Reaction>>) -> Vec<u8> {
+    let mut stack: Vec<u8> = Vec::with_capacity(polymer.len());
+    let mut positions: Vec<usize> = Vec::new();
+
+    for (i, &unit) in polymer.iter().enumerate() {
+        match stack.last() {
+            Some(&last) if rules.reacts(last, unit) => {
+                stack.pop();
+                if let Some(reactions) = trace.as_deref_mut() {
+                    reactions.push(Reaction {
+                        left: positions.pop().unwrap(),
+                        right: i,
+                        units: (last, unit),
+                    });
+                }
+            }
+            _ => {
+                stack.push(unit);
+                if trace.is_some() {
+                    positions.push(i);
+                }
+            }
+        }
+    }
+
+    stack
+}
+
+// Under the polarity rule removing a unit type commutes with reacting, so each
+// removal starts from the already reduced polymer. Removals run on their own
+// threads and the shortest result is returned with the unit removed.
+pub fn best_removal(reduced: &[u8]) -> Option<(u8, usize)> {
+    let mut units: Vec<u8> = reduced.iter().map(|u| u.to_ascii_lowercase()).collect();
+    units.sort();
+    units.dedup();
+
+    let rules = Rules::default();
+    thread::scope(|scope| {
+        let handles: Vec<_> = units
+            .iter()
+            .map(|&unit| {
+                let rules = &rules;
+                scope.spawn(move || {
+                    let removed: Vec<u8> = reduced
+                        .iter()
+                        .cloned()
+                        .filter(|u| !u.eq_ignore_ascii_case(&unit))
+                        .collect();
+                    (unit, reduce(&removed, rules, None).len())
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .min_by_key(|(unit, len)| (*len, *unit))
+    })
+}
@@ -2,143 +2,211 @@ use relative_path::RelativePath;
 use std::env;
 use std::fs;
 use std::time::Instant;
-use std::collections::HashMap;
+
+mod metric;
+mod region;
+mod render;
+mod voronoi;
+
+use metric::{Manhattan, Metric};
+use voronoi::Point;
 
 fn part1(contents: String) -> i64 {
-    let mut max_x: i64 = i64::MIN;
-    let mut max_y: i64 = i64::MIN;
-    let mut min_x: i64 = i64::MAX;
-    let mut min_y: i64 = i64::MAX;
+    voronoi::largest_finite_area(&parse_points(&contents), &Manhattan).unwrap_or(0) as i64
+}
 
-    let mut points: Vec<(i64, i64)> = Vec::new();
+fn part2(contents: String, max_total_dist: i64) -> i64 {
+    Manhattan.safe_region_size(&parse_points(&contents), max_total_dist) as i64
+}
 
-    for line in contents.lines() {
-        let parts: Vec<&str> = line.split(", ").collect();
-        let x: i64 = parts[0].parse().unwrap();
-        let y: i64 = parts[1].parse().unwrap();
+fn parse_points(contents: &str) -> Vec<Point> {
+    contents
+        .lines()
+        .map(|line| {
+            let (x, y) = line.split_once(", ").unwrap();
+            (x.trim().parse().unwrap(), y.trim().parse().unwrap())
+        })
+        .collect()
+}
 
-        if x > max_x {
-            max_x = x;
-        }
-        if x < min_x {
-            min_x = x;
-        }
-        if y > max_y {
-            max_y = y;
-        }
-        if y < min_y {
-            min_y = y;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metric::{Chebyshev, SquaredEuclidean};
+
+    #[test]
+    fn p1_test() {
+        let contents =
+            fs::read_to_string("example.txt").expect("Should have been able to read the file");
+
+        assert_eq!(part1(contents), 17);
+    }
+
+    #[test]
+    fn p2_test() {
+        let contents =
+            fs::read_to_string("example.txt").expect("Should have been able to read the file");
 
-        points.push((x, y));
+        assert_eq!(part2(contents, 32), 16);
     }
 
+    const EXAMPLE: &str = "1, 1\n1, 6\n8, 3\n3, 4\n5, 5\n8, 9";
 
-    let mut area_sizes: HashMap<(i64, i64), (bool, i64)> = HashMap::from_iter(points.iter().map(|&p| (p, (true, 0))).collect::<Vec<_>>());
+    #[test]
+    fn voronoi_test() {
+        let points = parse_points(EXAMPLE);
 
-    for y in min_y..=max_y {
-        for x in min_x..=max_x {
-            let mut min_dist: i64 = i64::MAX;
-            let mut min_point: (i64, i64) = (0, 0);
-            let mut is_tie: bool = false;
+        assert_eq!(Manhattan.infinite_regions(&points), vec![true, true, true, false, false, true]);
+        assert_eq!(part1(EXAMPLE.to_string()), 17);
+    }
 
-            for point in &points {
-                let dist = (x - point.0).abs() + (y - point.1).abs();
-                if dist < min_dist {
-                    min_dist = dist;
-                    min_point = *point;
-                    is_tie = false;
-                } else if dist == min_dist {
-                    is_tie = true;
-                }
-            }
+    #[test]
+    fn flood_fill_test() {
+        let points: Vec<Point> = (0..40).map(|i: i64| ((i * 37) % 23, (i * 11) % 19)).collect();
+        let (min_x, min_y, max_x, max_y) = voronoi::bounds(&points);
+        let labels = voronoi::flood_fill(&points, &[(-1, 0), (1, 0), (0, -1), (0, 1)], (min_x, min_y, max_x, max_y));
 
-            if !is_tie {
-                if x == min_x || x == max_x || y == min_y || y == max_y {
-                    area_sizes.insert(min_point, (false, 0));
-                } else {
-                    let (inside, size) = area_sizes.get(&min_point).unwrap();
-                    if *inside {
-                        area_sizes.insert(min_point, (true, size + 1));
-                    }
-                }
+        let mut brute: Vec<Option<usize>> = Vec::new();
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let dists: Vec<i64> = points.iter().map(|p| (x - p.0).abs() + (y - p.1).abs()).collect();
+                let min = *dists.iter().min().unwrap();
+                let closest: Vec<usize> = (0..points.len()).filter(|&i| dists[i] == min).collect();
+                brute.push(if closest.len() == 1 { Some(closest[0]) } else { None });
             }
         }
-    }
 
-    return area_sizes.iter().filter(|(_, v)| v.0).map(|(_, v)| v.1).max().unwrap();
-}
+        assert_eq!(labels, brute);
+    }
 
-fn part2(contents: String, max_total_dist: i64) -> i64 {
-    let mut max_x: i64 = i64::MIN;
-    let mut max_y: i64 = i64::MIN;
-    let mut min_x: i64 = i64::MAX;
-    let mut min_y: i64 = i64::MAX;
+    #[test]
+    fn safe_region_test() {
+        let points = parse_points(EXAMPLE);
 
-    let mut points: Vec<(i64, i64)> = Vec::new();
+        assert_eq!(part2(EXAMPLE.to_string(), 32), 16);
+        assert_eq!(Manhattan.safe_region_size(&points, 0), 0);
+        assert_eq!(Manhattan.safe_region_size(&[(3, 3)], 1), 1);
+        assert_eq!(Manhattan.safe_region_size(&[(3, 3)], 3), 13);
+        assert_eq!(Manhattan.safe_region_size(&[(0, 0), (0, 0)], 5), 13);
+        assert_eq!(Manhattan.safe_region_size(&[], 5), 0);
 
-    for line in contents.lines() {
-        let parts: Vec<&str> = line.split(", ").collect();
-        let x: i64 = parts[0].parse().unwrap();
-        let y: i64 = parts[1].parse().unwrap();
+        for threshold in [40, 150, 400, 1000] {
+            let mut brute = 0;
+            for y in -200..=210 {
+                for x in -200..=210 {
+                    let total: i64 = points.iter().map(|p| (x - p.0).abs() + (y - p.1).abs()).sum();
+                    if total < threshold {
+                        brute += 1;
+                    }
+                }
+            }
 
-        if x > max_x {
-            max_x = x;
-        }
-        if x < min_x {
-            min_x = x;
-        }
-        if y > max_y {
-            max_y = y;
-        }
-        if y < min_y {
-            min_y = y;
+            assert_eq!(Manhattan.safe_region_size(&points, threshold), brute);
         }
-
-        points.push((x, y));
     }
 
-    let mut region_size: i64 = 0;
-    for y in min_y..=max_y {
-        for x in min_x..=max_x {
-            let total_dist: i64 = points.iter().map(|p| (x - p.0).abs() + (y - p.1).abs()).sum();
-            if total_dist < max_total_dist {
-                region_size += 1;
+    // Labels a box much larger than the points by brute force; regions that
+    // reach its edge are taken to be infinite
+    fn brute_regions(points: &[Point], metric: &dyn Metric, reach: i64) -> (Vec<Option<usize>>, Vec<bool>) {
+        let mut areas = vec![0; points.len()];
+        let mut infinite = vec![false; points.len()];
+        for y in -reach..=reach {
+            for x in -reach..=reach {
+                let dists: Vec<i64> = points.iter().map(|p| metric.dist((x, y), *p)).collect();
+                let min = *dists.iter().min().unwrap();
+                let closest: Vec<usize> = (0..points.len()).filter(|&i| dists[i] == min).collect();
+                if let [owner] = closest[..] {
+                    areas[owner] += 1;
+                    if x.abs() == reach || y.abs() == reach {
+                        infinite[owner] = true;
+                    }
+                }
             }
         }
+
+        let areas = (0..points.len()).map(|i| (!infinite[i]).then_some(areas[i])).collect();
+        (areas, infinite)
     }
 
-    return region_size;
-}
+    #[test]
+    fn metrics_test() {
+        let sets: Vec<Vec<Point>> = vec![
+            parse_points(EXAMPLE),
+            vec![(0, 0), (10, 0), (5, 4), (5, 9), (2, 12), (14, 7), (5, 5), (8, 8)],
+            vec![(3, 3), (3, 8), (8, 3), (8, 8), (5, 6), (6, 5)],
+        ];
+        let metrics: Vec<Box<dyn Metric>> = vec![Box::new(Manhattan), Box::new(Chebyshev), Box::new(SquaredEuclidean)];
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        for points in &sets {
+            for metric in &metrics {
+                let (areas, infinite) = brute_regions(points, metric.as_ref(), 120);
+                assert_eq!(metric.infinite_regions(points), infinite);
+                assert_eq!(metric.finite_areas(points), areas);
 
-    #[test]
-    fn p1_test() {
-        let contents =
-            fs::read_to_string("example.txt").expect("Should have been able to read the file");
+                for threshold in [20, 60, 300] {
+                    let mut brute = 0;
+                    for y in -120..=120 {
+                        for x in -120..=120 {
+                            if points.iter().map(|p| metric.dist((x, y), *p)).sum::<i64>() < threshold {
+                                brute += 1;
+                            }
+                        }
+                    }
+                    assert_eq!(metric.safe_region_size(points, threshold), brute);
+                }
+            }
+        }
 
-        assert_eq!(part1(contents), 17);
+        for metric in &metrics {
+            assert_eq!(metric.safe_region_size(&[], 50), 0);
+        }
+
+        // One point's Chebyshev region is a square, and the walk out from
+        // the points mustn't grow with the threshold
+        let t: i128 = 100_000_000;
+        assert_eq!(Chebyshev.safe_region_size(&[(3, -2)], t as i64), (2 * t - 1) * (2 * t - 1));
+        assert_eq!(Chebyshev.safe_region_size(&[(0, 0), (0, 0)], t as i64), (t - 1) * (t - 1));
+        let far = Chebyshev.safe_region_size(&[(0, 0), (5, 5)], t as i64);
+        assert!((t - 5) * (t - 5) < far && far < (t + 5) * (t + 5));
     }
 
     #[test]
-    fn p2_test() {
-        let contents =
-            fs::read_to_string("example.txt").expect("Should have been able to read the file");
+    fn render_test() {
+        let points = parse_points(EXAMPLE);
+        let ppm = render::render(&points, &Chebyshev, 32, 3);
 
-        assert_eq!(part2(contents, 32), 16);
+        assert!(ppm.starts_with(b"P6\n36 39\n255\n"));
+        assert_eq!(ppm.len(), b"P6\n36 39\n255\n".len() + 36 * 39 * 3);
+
+        // With no safe region to wash anything out, the seed at (1, 1) is
+        // the block of pixels from (6, 6), drawn as a shade of its region
+        let plain = render::render(&points, &Chebyshev, 0, 3);
+        let header = b"P6\n36 39\n255\n".len();
+        let pixel = |x: usize, y: usize| &plain[header + (y * 36 + x) * 3..header + (y * 36 + x) * 3 + 3];
+        assert_eq!(pixel(7, 7), render::seed_colour(0, points.len()));
+        assert_eq!(pixel(7, 4), render::region_colour(0, points.len()));
+        assert_ne!(render::seed_colour(0, points.len()), render::seed_colour(1, points.len()));
+
+        for count in [1, 6, 50, 240, 241, 720] {
+            let mut colours: Vec<[u8; 3]> = (0..count)
+                .flat_map(|i| [render::region_colour(i, count), render::seed_colour(i, count)])
+                .collect();
+            colours.sort();
+            colours.dedup();
+            assert_eq!(colours.len(), 2 * count);
+        }
     }
 }
 fn main() {
     let args: Vec<String> = env::args().collect();
+    let (flags, paths): (Vec<String>, Vec<String>) =
+        args.iter().skip(1).cloned().partition(|arg| arg.starts_with("--"));
     let year = "2018".to_string();
     let day = "6".to_string();
 
     let root = env::current_dir().unwrap();
-    let path_str = if args.len() > 1 {
-        args[1].clone()
+    let path_str = if !paths.is_empty() {
+        paths[0].clone()
     } else if root.ends_with(format!("rust_{}_{}", year, day)) {
         format!("../../../Inputs/{}_{}.txt", year, day)
     } else {
@@ -146,9 +214,14 @@ fn main() {
     };
 
 
-    let contents = fs::read_to_string(if args.len() > 1 {path_str} else {RelativePath::new(&path_str).to_path(&root).display().to_string()})
+    let contents = fs::read_to_string(if !paths.is_empty() {path_str} else {RelativePath::new(&path_str).to_path(&root).display().to_string()})
         .expect("Should have been able to read the file");
 
+    let threshold = flags
+        .iter()
+        .find_map(|flag| flag.strip_prefix("--threshold="))
+        .map_or(10000, |n| n.parse::<i64>().expect("Should have been a number"));
+
     let part1_timer = Instant::now();
     println!(
         "\nPart 1:\nLargest finite area: {}\nRan in {:.5?}",
@@ -158,8 +231,36 @@ fn main() {
 
     let part2_timer = Instant::now();
     println!(
-        "\nPart 2:\nRegion within 10000 total units of all points: {}\nRan in {:.5?}",
-        part2(contents.clone(), 10000),
+        "\nPart 2:\nRegion within {} total units of all points: {}\nRan in {:.5?}",
+        threshold,
+        part2(contents.clone(), threshold),
         part2_timer.elapsed()
     );
+
+    let metric_name = flags
+        .iter()
+        .find_map(|flag| flag.strip_prefix("--metric="))
+        .unwrap_or("manhattan");
+    let metric = metric::from_name(metric_name).expect("Should have been manhattan, chebyshev or euclidean");
+    let points = parse_points(&contents);
+    let scale = flags
+        .iter()
+        .find_map(|flag| flag.strip_prefix("--scale="))
+        .map_or(1, |n| n.parse::<usize>().expect("Should have been a number"));
+
+    for flag in &flags {
+        match flag.split_once('=') {
+            Some(("--metric", _)) => {
+                println!("\nUsing {} distance:", metric_name);
+                println!("Largest finite area: {}", voronoi::largest_finite_area(&points, metric.as_ref()).unwrap_or(0));
+                println!("Region within {} total units of all points: {}", threshold, metric.safe_region_size(&points, threshold));
+            }
+            Some(("--render", path)) => {
+                fs::write(path, render::render(&points, metric.as_ref(), threshold, scale)).expect("Should have been able to write the image");
+                println!("\nWrote regions to {}", path);
+            }
+            Some(("--threshold", _)) | Some(("--scale", _)) => {}
+            _ => panic!("Unknown option {}", flag),
+        }
+    }
 }
\ No newline at end of file
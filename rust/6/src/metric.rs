@@ -0,0 +1,118 @@
+use crate::region;
+use crate::voronoi::{self, Extent, Point};
+
+const ORTHOGONAL_STEPS: [(i64, i64); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+const KING_STEPS: [(i64, i64); 8] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+
+pub trait Metric {
+    fn dist(&self, a: Point, b: Point) -> i64;
+
+    // Which points have a region that goes on forever
+    fn infinite_regions(&self, points: &[Point]) -> Vec<bool>;
+
+    // Size of each point's region, or None where it's infinite
+    fn finite_areas(&self, points: &[Point]) -> Vec<Option<usize>>;
+
+    // Cells whose total distance to every point is under the threshold
+    fn safe_region_size(&self, points: &[Point], threshold: i64) -> i128;
+}
+
+pub fn from_name(name: &str) -> Option<Box<dyn Metric>> {
+    match name {
+        "manhattan" => Some(Box::new(Manhattan)),
+        "chebyshev" => Some(Box::new(Chebyshev)),
+        "euclidean" => Some(Box::new(SquaredEuclidean)),
+        _ => None,
+    }
+}
+
+fn mask_infinite(areas: Vec<usize>, infinite: Vec<bool>) -> Vec<Option<usize>> {
+    areas
+        .into_iter()
+        .zip(infinite)
+        .map(|(area, infinite)| if infinite { None } else { Some(area) })
+        .collect()
+}
+
+pub struct Manhattan;
+
+impl Metric for Manhattan {
+    fn dist(&self, a: Point, b: Point) -> i64 {
+        (a.0 - b.0).abs() + (a.1 - b.1).abs()
+    }
+
+    fn infinite_regions(&self, points: &[Point]) -> Vec<bool> {
+        voronoi::envelope_infinite(points)
+    }
+
+    // Finite regions never leave the bounding box
+    fn finite_areas(&self, points: &[Point]) -> Vec<Option<usize>> {
+        let labels = voronoi::flood_fill(points, &ORTHOGONAL_STEPS, voronoi::bounds(points));
+        mask_infinite(voronoi::region_areas(&labels, points.len()), self.infinite_regions(points))
+    }
+
+    fn safe_region_size(&self, points: &[Point], threshold: i64) -> i128 {
+        region::manhattan_region_size(points, threshold)
+    }
+}
+
+// Chebyshev distance is half the Manhattan distance between the points
+// rotated to (x + y, x - y), so regions behave like Manhattan ones there.
+pub struct Chebyshev;
+
+pub fn rotate(points: &[Point]) -> Vec<Point> {
+    points.iter().map(|p| (p.0 + p.1, p.0 - p.1)).collect()
+}
+
+impl Metric for Chebyshev {
+    fn dist(&self, a: Point, b: Point) -> i64 {
+        (a.0 - b.0).abs().max((a.1 - b.1).abs())
+    }
+
+    fn infinite_regions(&self, points: &[Point]) -> Vec<bool> {
+        voronoi::envelope_infinite(&rotate(points))
+    }
+
+    // Finite regions stay inside the rotated bounding box, a diamond here
+    fn finite_areas(&self, points: &[Point]) -> Vec<Option<usize>> {
+        let (min_u, min_v, max_u, max_v) = voronoi::bounds(&rotate(points));
+        let extent: Extent = (
+            (min_u + min_v).div_euclid(2),
+            (min_u - max_v).div_euclid(2),
+            (max_u + max_v + 1).div_euclid(2),
+            (max_u - min_v + 1).div_euclid(2),
+        );
+
+        let labels = voronoi::flood_fill(points, &KING_STEPS, extent);
+        mask_infinite(voronoi::region_areas(&labels, points.len()), self.infinite_regions(points))
+    }
+
+    fn safe_region_size(&self, points: &[Point], threshold: i64) -> i128 {
+        region::chebyshev_region_size(points, threshold)
+    }
+}
+
+// Same regions as Euclidean distance, but stays in integers
+pub struct SquaredEuclidean;
+
+impl Metric for SquaredEuclidean {
+    fn dist(&self, a: Point, b: Point) -> i64 {
+        (a.0 - b.0).pow(2) + (a.1 - b.1).pow(2)
+    }
+
+    fn infinite_regions(&self, points: &[Point]) -> Vec<bool> {
+        voronoi::hull_members(points)
+    }
+
+    fn finite_areas(&self, points: &[Point]) -> Vec<Option<usize>> {
+        self.infinite_regions(points)
+            .into_iter()
+            .enumerate()
+            .map(|(i, infinite)| (!infinite).then(|| voronoi::euclidean_area(points, i)))
+            .collect()
+    }
+
+    fn safe_region_size(&self, points: &[Point], threshold: i64) -> i128 {
+        region::squared_region_size(points, threshold)
+    }
+}
@@ -0,0 +1,179 @@
+use crate::metric::rotate;
+use crate::voronoi::Point;
+
+// Totals of |c - p| over one axis' coordinates, at every position or only
+// those of one parity. Inside the coordinates' span every total is kept,
+// sorted; past either end the total just grows by the point count per step,
+// so each side is a first total and how much it grows between positions.
+struct AxisSums {
+    inside: Vec<i128>,
+    tails: [(i128, i128); 2],
+}
+
+impl AxisSums {
+    fn new(coords: &[i64], parity: Option<i64>) -> AxisSums {
+        let mut sorted = coords.to_vec();
+        sorted.sort();
+        let n = sorted.len() as i128;
+        if sorted.is_empty() {
+            return AxisSums {
+                inside: Vec::new(),
+                tails: [(0, 1), (0, 1)],
+            };
+        }
+
+        let (lo, hi) = (sorted[0], sorted[sorted.len() - 1]);
+        let wanted = |c: i64| parity.is_none_or(|p| c.rem_euclid(2) == p);
+
+        let mut inside: Vec<i128> = Vec::new();
+        let mut edges: [i128; 2] = [0, 0];
+        let mut sum: i128 = sorted.iter().map(|&p| (p - lo) as i128).sum();
+        let mut below: usize = 0;
+        for c in lo..=hi {
+            while below < sorted.len() && sorted[below] <= c {
+                below += 1;
+            }
+
+            if c == lo {
+                edges[0] = sum;
+            }
+            if c == hi {
+                edges[1] = sum;
+            }
+            if wanted(c) {
+                inside.push(sum);
+            }
+            sum += 2 * below as i128 - n;
+        }
+        inside.sort();
+
+        // The first position past each end that's wanted, one or two steps out
+        let stride = if parity.is_some() { 2 } else { 1 };
+        let first = |c: i64| if wanted(c) { 1 } else { 2 };
+        let tails = [
+            (edges[0] + n * first(lo - 1), n * stride),
+            (edges[1] + n * first(hi + 1), n * stride),
+        ];
+        AxisSums { inside, tails }
+    }
+
+    // Positions along the axis with a total under the budget
+    fn count_under(&self, budget: i128) -> i128 {
+        let inside = self.inside.partition_point(|&s| s < budget) as i128;
+        let tails: i128 = self
+            .tails
+            .iter()
+            .map(|&(first, step)| if budget > first { (budget - first - 1) / step + 1 } else { 0 })
+            .sum();
+
+        inside + tails
+    }
+}
+
+// Pairs of one total from each axis that add up to under the budget, where
+// both axes' tails grow by the same step. Along an x tail the budget left
+// for y drops by that step each time, and once it's beyond every inside y
+// total and the start of both y tails, the y count is linear in the step,
+// so those positions are summed in closed form.
+fn count_pairs_under(xs: &AxisSums, ys: &AxisSums, budget: i128) -> i128 {
+    let mut count: i128 = xs.inside.iter().map(|&a| ys.count_under(budget - a)).sum();
+    let linear_from = ys.tails.iter().map(|&(first, _)| first).chain(ys.inside.last().copied()).max().unwrap();
+
+    for &(first, step) in &xs.tails {
+        let steps = if budget > first { (budget - first - 1) / step + 1 } else { 0 };
+        let closed = ((budget - first - linear_from - 1).div_euclid(step) + 1).clamp(0, steps);
+
+        count += closed * ys.inside.len() as i128;
+        for &(first_y, _) in &ys.tails {
+            let most = (budget - first - first_y - 1).div_euclid(step) + 1;
+            count += closed * most - closed * (closed - 1) / 2;
+        }
+
+        for k in closed..steps {
+            count += ys.count_under(budget - first - step * k);
+        }
+    }
+
+    count
+}
+
+// Number of cells whose total Manhattan distance to all the points is under
+// the threshold, wherever they are. The total splits into an x part and a y
+// part, so this counts pairs of per-axis totals.
+pub fn manhattan_region_size(points: &[Point], threshold: i64) -> i128 {
+    // With no points there's nothing to be near
+    if points.is_empty() {
+        return 0;
+    }
+
+    let xs = AxisSums::new(&points.iter().map(|p| p.0).collect::<Vec<i64>>(), None);
+    let ys = AxisSums::new(&points.iter().map(|p| p.1).collect::<Vec<i64>>(), None);
+    count_pairs_under(&xs, &ys, threshold as i128)
+}
+
+// Totals of term(c - p) over the coordinates for every integer c where that's
+// under the budget. The term must be convex, so totals only grow moving out
+// from the coordinates' span.
+fn axis_totals(coords: &[i64], term: fn(i64) -> i128, budget: i128) -> Vec<i128> {
+    let total = |c: i64| coords.iter().map(|&p| term(c - p)).sum::<i128>();
+    let lo = *coords.iter().min().unwrap();
+    let hi = *coords.iter().max().unwrap();
+
+    let mut totals: Vec<i128> = (lo..=hi).map(total).filter(|&t| t < budget).collect();
+    for (start, step) in [(lo - 1, -1), (hi + 1, 1)] {
+        let mut c = start;
+        while total(c) < budget {
+            totals.push(total(c));
+            c += step;
+        }
+    }
+
+    totals
+}
+
+// Pairs of one total from each list that add up to under the budget
+fn count_pairs(mut xs: Vec<i128>, mut ys: Vec<i128>, budget: i128) -> i128 {
+    xs.sort();
+    ys.sort();
+
+    let mut j = ys.len();
+    let mut count: i128 = 0;
+    for a in xs {
+        while j > 0 && ys[j - 1] >= budget - a {
+            j -= 1;
+        }
+        count += j as i128;
+    }
+
+    count
+}
+
+// In rotated coordinates the total Chebyshev distance is half the sum of the
+// per-axis totals, but only cells with u and v of the same parity exist.
+pub fn chebyshev_region_size(points: &[Point], threshold: i64) -> i128 {
+    if points.is_empty() {
+        return 0;
+    }
+
+    let rotated = rotate(points);
+    let us: Vec<i64> = rotated.iter().map(|p| p.0).collect();
+    let vs: Vec<i64> = rotated.iter().map(|p| p.1).collect();
+    (0..2)
+        .map(|parity| {
+            let (us, vs) = (AxisSums::new(&us, Some(parity)), AxisSums::new(&vs, Some(parity)));
+            count_pairs_under(&us, &vs, 2 * threshold as i128)
+        })
+        .sum()
+}
+
+pub fn squared_region_size(points: &[Point], threshold: i64) -> i128 {
+    if points.is_empty() {
+        return 0;
+    }
+
+    let square = |d: i64| (d as i128) * (d as i128);
+    let xs = axis_totals(&points.iter().map(|p| p.0).collect::<Vec<i64>>(), square, threshold as i128);
+    let ys = axis_totals(&points.iter().map(|p| p.1).collect::<Vec<i64>>(), square, threshold as i128);
+
+    count_pairs(xs, ys, threshold as i128)
+}
@@ -0,0 +1,95 @@
+use crate::metric::Metric;
+use crate::voronoi::{self, Point};
+
+const TIED: [u8; 3] = [40, 40, 40];
+
+// Region colours share one saturation, so they differ by hue and, past
+// HUES regions, by brightness. Seeds are their region's colour at well under
+// the brightness of any region.
+const HUES: usize = 240;
+const BRIGHTNESS: [f64; 3] = [0.9, 0.75, 0.6];
+const SATURATION: f64 = 0.65;
+const SEED_SHADE: f64 = 0.6;
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+// h is in sixths of the way round the colour wheel
+fn hsv(h: f64, v: f64) -> [u8; 3] {
+    let c = v * SATURATION;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as usize {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+
+    [r, g, b].map(|channel| ((channel + v - c) * 255.0).round() as u8)
+}
+
+// Hues evenly spaced around the wheel, handed out by a stride near the
+// golden ratio that shares no factor with the number of hues. Every region
+// gets its own hue, and neighbouring indices land far apart. Region and
+// seed colours are all distinct for up to HUES * 3 regions.
+fn region_hsv(i: usize, count: usize) -> (f64, f64) {
+    let hues = count.clamp(1, HUES);
+    let stride = ((hues as f64 * 0.618_033_988_75).round() as usize..)
+        .find(|&stride| gcd(stride, hues) == 1)
+        .unwrap();
+    let h = ((i % hues) * stride % hues) as f64 / hues as f64 * 6.0;
+    (h, BRIGHTNESS[i / hues % BRIGHTNESS.len()])
+}
+
+pub fn region_colour(i: usize, count: usize) -> [u8; 3] {
+    let (h, v) = region_hsv(i, count);
+    hsv(h, v)
+}
+
+pub fn seed_colour(i: usize, count: usize) -> [u8; 3] {
+    let (h, v) = region_hsv(i, count);
+    hsv(h, v * SEED_SHADE)
+}
+
+// PPM of the labelled regions around the points, with the safe region washed
+// out towards white. Each cell becomes a scale x scale block of pixels.
+pub fn render(points: &[Point], metric: &dyn Metric, threshold: i64, scale: usize) -> Vec<u8> {
+    let (min_x, min_y, max_x, max_y) = voronoi::bounds(points);
+    let margin = ((max_x - min_x).max(max_y - min_y) / 10).max(2);
+    let (min_x, min_y, max_x, max_y) = (min_x - margin, min_y - margin, max_x + margin, max_y + margin);
+    let scale = scale.max(1);
+    let width = (max_x - min_x + 1) as usize * scale;
+    let height = (max_y - min_y + 1) as usize * scale;
+
+    let mut cells: Vec<[u8; 3]> = Vec::new();
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            let dists: Vec<i64> = points.iter().map(|p| metric.dist((x, y), *p)).collect();
+            let closest = *dists.iter().min().unwrap();
+            let owners: Vec<usize> = (0..points.len()).filter(|&i| dists[i] == closest).collect();
+
+            let mut colour = match owners[..] {
+                [owner] if closest == 0 => seed_colour(owner, points.len()),
+                [owner] => region_colour(owner, points.len()),
+                _ => TIED,
+            };
+            if dists.iter().sum::<i64>() < threshold {
+                colour = colour.map(|channel| ((channel as u16 + 255) / 2) as u8);
+            }
+            cells.push(colour);
+        }
+    }
+
+    let mut ppm = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    let row_cells = width / scale;
+    for py in 0..height {
+        for px in 0..width {
+            ppm.extend(cells[(py / scale) * row_cells + px / scale]);
+        }
+    }
+
+    ppm
+}
@@ -0,0 +1,206 @@
+use crate::metric::Metric;
+use std::collections::VecDeque;
+
+pub type Point = (i64, i64);
+
+// min_x, min_y, max_x, max_y
+pub type Extent = (i64, i64, i64, i64);
+
+pub fn bounds(points: &[Point]) -> Extent {
+    (
+        points.iter().map(|p| p.0).min().unwrap(),
+        points.iter().map(|p| p.1).min().unwrap(),
+        points.iter().map(|p| p.0).max().unwrap(),
+        points.iter().map(|p| p.1).max().unwrap(),
+    )
+}
+
+// Labels every cell in the extent, row by row, with its closest point, for
+// metrics where distance is the number of steps taken. Multi-source BFS from
+// every point at once: a cell reached in the same round from two different
+// points, or from a tied cell, is itself tied and gets no label.
+pub fn flood_fill(points: &[Point], steps: &[(i64, i64)], extent: Extent) -> Vec<Option<usize>> {
+    let (min_x, min_y, max_x, max_y) = extent;
+    let width = (max_x - min_x + 1) as usize;
+    let height = (max_y - min_y + 1) as usize;
+
+    let mut dists: Vec<usize> = vec![usize::MAX; width * height];
+    let mut labels: Vec<Option<usize>> = vec![None; width * height];
+    let mut queue: VecDeque<usize> = VecDeque::new();
+
+    for (i, p) in points.iter().enumerate() {
+        let cell = (p.1 - min_y) as usize * width + (p.0 - min_x) as usize;
+        if dists[cell] == 0 {
+            labels[cell] = None;
+        } else {
+            dists[cell] = 0;
+            labels[cell] = Some(i);
+            queue.push_back(cell);
+        }
+    }
+
+    while let Some(cell) = queue.pop_front() {
+        let (x, y) = ((cell % width) as i64, (cell / width) as i64);
+        let neighbours = steps.iter().filter_map(|(dx, dy)| {
+            let (nx, ny) = (x + dx, y + dy);
+            let inside = (0..width as i64).contains(&nx) && (0..height as i64).contains(&ny);
+            inside.then(|| ny as usize * width + nx as usize)
+        });
+
+        for next in neighbours {
+            if dists[next] == usize::MAX {
+                dists[next] = dists[cell] + 1;
+                labels[next] = labels[cell];
+                queue.push_back(next);
+            } else if dists[next] == dists[cell] + 1 && labels[next] != labels[cell] {
+                labels[next] = None;
+            }
+        }
+    }
+
+    labels
+}
+
+pub fn region_areas(labels: &[Option<usize>], count: usize) -> Vec<usize> {
+    let mut areas = vec![0; count];
+    for label in labels.iter().flatten() {
+        areas[*label] += 1;
+    }
+
+    areas
+}
+
+pub fn largest_finite_area(points: &[Point], metric: &dyn Metric) -> Option<usize> {
+    metric.finite_areas(points).into_iter().flatten().max()
+}
+
+fn unique_max(values: impl Iterator<Item = i64>) -> Option<usize> {
+    let mut best: Option<(usize, i64)> = None;
+    let mut tied = false;
+    for (i, value) in values.enumerate() {
+        match best {
+            Some((_, b)) if value < b => {}
+            Some((_, b)) if value == b => tied = true,
+            _ => {
+                best = Some((i, value));
+                tied = false;
+            }
+        }
+    }
+
+    best.filter(|_| !tied).map(|(i, _)| i)
+}
+
+// Manhattan regions. Far along +x in row c a point's distance is
+// x - px + |c - py|, so the cell there belongs to whoever uniquely maximises
+// px - |c - py|, and the same owner holds the rest of that ray. Any infinite
+// region must own such a ray in one of the four directions, and past the
+// points' extent the winner of each direction stops changing, so checking
+// one row or column beyond each side is enough.
+pub fn envelope_infinite(points: &[Point]) -> Vec<bool> {
+    let (min_x, min_y, max_x, max_y) = bounds(points);
+    let mut infinite = vec![false; points.len()];
+
+    for sign in [1, -1] {
+        for c in (min_y - 1)..=(max_y + 1) {
+            if let Some(i) = unique_max(points.iter().map(|p| sign * p.0 - (c - p.1).abs())) {
+                infinite[i] = true;
+            }
+        }
+
+        for c in (min_x - 1)..=(max_x + 1) {
+            if let Some(i) = unique_max(points.iter().map(|p| sign * p.1 - (c - p.0).abs())) {
+                infinite[i] = true;
+            }
+        }
+    }
+
+    infinite
+}
+
+fn cross(o: Point, a: Point, b: Point) -> i128 {
+    (a.0 - o.0) as i128 * (b.1 - o.1) as i128 - (a.1 - o.1) as i128 * (b.0 - o.0) as i128
+}
+
+// Points on the convex hull's boundary, including those partway along an
+// edge. Under Euclidean distance exactly these have unbounded regions.
+pub fn hull_members(points: &[Point]) -> Vec<bool> {
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    order.sort_by_key(|&i| points[i]);
+
+    let mut members = vec![false; points.len()];
+    for pass in [order.clone(), order.into_iter().rev().collect()] {
+        let mut chain: Vec<usize> = Vec::new();
+        for i in pass {
+            while chain.len() >= 2
+                && cross(points[chain[chain.len() - 2]], points[chain[chain.len() - 1]], points[i]) < 0
+            {
+                chain.pop();
+            }
+            chain.push(i);
+        }
+
+        for i in chain {
+            members[i] = true;
+        }
+    }
+
+    members
+}
+
+fn clip(polygon: &[(f64, f64)], a: f64, b: f64, c: f64) -> Vec<(f64, f64)> {
+    let side = |p: (f64, f64)| a * p.0 + b * p.1 - c;
+    let mut clipped: Vec<(f64, f64)> = Vec::new();
+    for (k, &cur) in polygon.iter().enumerate() {
+        let next = polygon[(k + 1) % polygon.len()];
+        let (fc, fn_) = (side(cur), side(next));
+        if fc <= 0.0 {
+            clipped.push(cur);
+        }
+        if (fc < 0.0 && fn_ > 0.0) || (fc > 0.0 && fn_ < 0.0) {
+            let t = fc / (fc - fn_);
+            clipped.push((cur.0 + t * (next.0 - cur.0), cur.1 + t * (next.1 - cur.1)));
+        }
+    }
+
+    clipped
+}
+
+// Cells of a bounded Euclidean region. The region is the intersection of the
+// half-planes closer to the point than to each other point, which bounds the
+// cells worth checking; each of those is then checked exactly.
+pub fn euclidean_area(points: &[Point], i: usize) -> usize {
+    let p = points[i];
+    if points.iter().enumerate().any(|(j, q)| j != i && *q == p) {
+        return 0;
+    }
+
+    let far = 1e12;
+    let (px, py) = (p.0 as f64, p.1 as f64);
+    let mut polygon = vec![(px - far, py - far), (px + far, py - far), (px + far, py + far), (px - far, py + far)];
+    for (_, q) in points.iter().enumerate().filter(|(j, _)| *j != i) {
+        let (qx, qy) = (q.0 as f64, q.1 as f64);
+        polygon = clip(&polygon, 2.0 * (qx - px), 2.0 * (qy - py), qx * qx + qy * qy - px * px - py * py);
+    }
+    if polygon.is_empty() {
+        return 0;
+    }
+
+    let min_x = polygon.iter().map(|v| v.0).fold(f64::MAX, f64::min).floor() as i64 - 1;
+    let max_x = polygon.iter().map(|v| v.0).fold(f64::MIN, f64::max).ceil() as i64 + 1;
+    let min_y = polygon.iter().map(|v| v.1).fold(f64::MAX, f64::min).floor() as i64 - 1;
+    let max_y = polygon.iter().map(|v| v.1).fold(f64::MIN, f64::max).ceil() as i64 + 1;
+
+    let dist = |a: Point, b: Point| (a.0 - b.0).pow(2) + (a.1 - b.1).pow(2);
+    let mut area = 0;
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            let own = dist((x, y), p);
+            if points.iter().enumerate().all(|(j, q)| j == i || dist((x, y), *q) > own) {
+                area += 1;
+            }
+        }
+    }
+
+    area
+}
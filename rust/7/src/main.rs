@@ -1,112 +1,79 @@
 use relative_path::RelativePath;
-use std::cmp::Reverse;
-use std::collections::BinaryHeap;
 use std::collections::HashMap;
-use std::collections::HashSet;
 use std::env;
 use std::fs;
 use std::time::Instant;
 
-fn part1(contents: String) -> String {
-    let mut steps: HashMap<char, Vec<char>> = HashMap::new();
-    let mut prereqs: HashMap<char, HashSet<char>> = HashMap::new();
-
-    for line in contents.lines() {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        let prereq = parts[1].chars().next().unwrap();
-        let step = parts[7].chars().next().unwrap();
-
-        steps.entry(step).or_insert(Vec::new());
-        prereqs.entry(prereq).or_insert(HashSet::new());
-
-        steps.entry(prereq).or_insert(Vec::new()).push(step);
-        prereqs.entry(step).or_insert(HashSet::new()).insert(prereq);
-    }
-
-    let mut available: BinaryHeap<Reverse<char>> = BinaryHeap::new();
-    for (step, prereq) in &prereqs {
-        if prereq.len() == 0 {
-            available.push(Reverse(*step));
-        }
-    }
-
-    let mut finished: HashSet<char> = HashSet::new();
-    let mut order = String::new();
-    while !available.is_empty() {
+mod report;
+mod scheduler;
 
-        let step = available.pop().unwrap().0;
-        order.push(step);
-        finished.insert(step);
+use scheduler::{Graph, Scheduler};
 
-        for next in steps.get(&step).unwrap() {
-            if finished.is_superset(&prereqs.get(next).unwrap()) {
-                available.push(Reverse(*next));
-            }
-        }
-    }
-
-    return order;
+fn part1(contents: String) -> String {
+    let graph = parse_graph(&contents);
+    let schedule = Scheduler::new(&graph).run().expect("Should have been a valid step graph");
+    order_string(&graph, &schedule)
 }
 
-fn part2(contents: String, num_workers: i64, base_time: i64) -> i64 {
-    let mut steps: HashMap<char, Vec<char>> = HashMap::new();
-    let mut prereqs: HashMap<char, HashSet<char>> = HashMap::new();
+fn part2(contents: String, num_workers: usize, base_time: i64) -> i64 {
+    let graph = parse_graph(&contents);
+    let schedule = Scheduler::new(&graph)
+        .workers(num_workers)
+        .durations(scheduler::letter_time(base_time))
+        .run()
+        .expect("Should have been a valid step graph");
 
-    for line in contents.lines() {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        let prereq = parts[1].chars().next().unwrap();
-        let step = parts[7].chars().next().unwrap();
-
-        steps.entry(step).or_insert(Vec::new());
-        prereqs.entry(prereq).or_insert(HashSet::new());
+    scheduler::finish_time(&schedule)
+}
 
-        steps.entry(prereq).or_insert(Vec::new()).push(step);
-        prereqs.entry(step).or_insert(HashSet::new()).insert(prereq);
-    }
+// Either "Step X must be finished before step Y can begin." with any names for
+// X and Y, or "Y: X Z", which defines Y. With no "Y:" lines at all the steps
+// are whatever the puzzle lines name; otherwise the puzzle lines only add
+// dependencies, and every step they name has to be defined by a "Y:" line.
+fn parse_graph(contents: &str) -> Graph {
+    let lines: Vec<&str> = contents.lines().filter(|line| !line.trim().is_empty()).collect();
+    let has_definitions = lines.iter().any(|line| line.contains(':'));
 
-    let mut available: BinaryHeap<Reverse<char>> = BinaryHeap::new();
-    for (step, prereq) in &prereqs {
-        if prereq.len() == 0 {
-            available.push(Reverse(*step));
-        }
-    }
-
-    let mut finished: HashSet<char> = HashSet::new();
-    let mut curr_time: i64 = 0;
-    let mut workers: BinaryHeap<Reverse<Worker>> = BinaryHeap::new();
-
-    while available.len() > 0 || workers.len() > 0 {
-        if workers.len() < num_workers as usize && available.len() > 0 {
-            let step = available.pop().unwrap().0;
-            workers.push(Reverse(Worker {
-                time: curr_time + base_time + (step as i64 - 'A' as i64) + 1,
-                step: step,
-            }));
+    let mut graph = Graph::new();
+    for line in lines {
+        if let Some((task, prereqs)) = line.split_once(':') {
+            graph.define(task.trim());
+            for prereq in prereqs.split_whitespace() {
+                graph.depend(prereq, task.trim());
+            }
         } else {
-            let finished_worker = workers.pop().unwrap().0;
-            curr_time = finished_worker.time;
-            finished.insert(finished_worker.step);
-
-            for next in steps.get(&finished_worker.step).unwrap() {
-                if finished.is_superset(&prereqs.get(next).unwrap()) {
-                    available.push(Reverse(*next));
-                }
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if !has_definitions {
+                graph.define(parts[1]);
+                graph.define(parts[7]);
             }
+            graph.depend(parts[1], parts[7]);
         }
     }
 
-    return curr_time;
+    graph
+}
+
+// Single letter names run together like the puzzle's answer, longer ones are
+// space separated
+fn order_string(graph: &Graph, schedule: &[scheduler::Assignment]) -> String {
+    let names: Vec<&str> = schedule.iter().map(|a| graph.name(a.task)).collect();
+    names.join(if names.iter().all(|n| n.len() == 1) { "" } else { " " })
 }
 
-#[derive(Eq, PartialEq, Ord, PartialOrd, Debug, Clone)]
-struct Worker {
-    time: i64,
-    step: char,
+// One "NAME SECONDS" pair per line
+fn parse_durations(contents: &str) -> HashMap<String, i64> {
+    contents
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .map(|(name, time)| (name.to_string(), time.trim().parse().expect("Should have been a number")))
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use scheduler::GraphError;
 
     #[test]
     fn p1_test() {
@@ -123,15 +90,148 @@ mod tests {
 
         assert_eq!(part2(contents, 2, 0), 15);
     }
+
+    const EXAMPLE: &str = "Step C must be finished before step A can begin.
+Step C must be finished before step F can begin.
+Step A must be finished before step B can begin.
+Step A must be finished before step D can begin.
+Step B must be finished before step E can begin.
+Step D must be finished before step E can begin.
+Step F must be finished before step E can begin.";
+
+    #[test]
+    fn scheduler_test() {
+        assert_eq!(part1(EXAMPLE.to_string()), "CABDFE");
+        assert_eq!(part2(EXAMPLE.to_string(), 2, 0), 15);
+
+        let graph = parse_graph(EXAMPLE);
+        let order = |tie_break: scheduler::TieBreak| order_string(&graph, &Scheduler::new(&graph).tie_break(tie_break).run().unwrap());
+        assert_eq!(order(scheduler::input_order), "CAFBDE");
+        assert_eq!(order(scheduler::most_dependents), "CABDFE");
+
+        let table = parse_durations("C 10\nA 1\nF 2");
+        let schedule = Scheduler::new(&graph)
+            .workers(2)
+            .durations(|name| table.get(name).copied().unwrap_or(5))
+            .run()
+            .unwrap();
+        assert_eq!(scheduler::finish_time(&schedule), 22);
+    }
+
+    #[test]
+    fn named_tasks_test() {
+        let graph = parse_graph(
+            "Step fetch must be finished before step build can begin.
+Step build must be finished before step test can begin.
+Step build must be finished before step docs can begin.
+Step lint must be finished before step test can begin.",
+        );
+
+        assert_eq!(order_string(&graph, &Scheduler::new(&graph).run().unwrap()), "fetch build docs lint test");
+        assert_eq!(
+            order_string(&graph, &Scheduler::new(&graph).workers(3).durations(|name| name.len() as i64).run().unwrap()),
+            "fetch lint build docs test"
+        );
+    }
+
+    #[test]
+    fn diagnostics_test() {
+        let graph = parse_graph(&(EXAMPLE.to_string() + "\nStep E must be finished before step A can begin.\nStep G must be finished before step G can begin."));
+        assert_eq!(
+            Scheduler::new(&graph).run(),
+            Err(vec![
+                GraphError::Cycle(vec!["A".to_string(), "B".to_string(), "E".to_string()]),
+                GraphError::Cycle(vec!["G".to_string()]),
+            ])
+        );
+        assert_eq!(
+            GraphError::Cycle(vec!["A".to_string(), "B".to_string(), "E".to_string()]).to_string(),
+            "cycle: A -> B -> E -> A"
+        );
+
+        let graph = parse_graph("build: fetch lint\ntest: build\nfetch:\ndocs: build render");
+        assert_eq!(
+            graph.check(),
+            Err(vec![
+                GraphError::Undefined { task: "lint".to_string(), needed_by: vec!["build".to_string()] },
+                GraphError::Undefined { task: "render".to_string(), needed_by: vec!["docs".to_string()] },
+            ])
+        );
+
+        let graph = parse_graph("build: fetch\nfetch:\nStep fetch must be finished before step build can begin.\nStep lint must be finished before step test can begin.");
+        assert_eq!(
+            graph.check(),
+            Err(vec![
+                GraphError::Undefined { task: "lint".to_string(), needed_by: vec!["test".to_string()] },
+                GraphError::Undefined { task: "test".to_string(), needed_by: vec![] },
+            ])
+        );
+        assert_eq!(
+            GraphError::Undefined { task: "test".to_string(), needed_by: vec![] }.to_string(),
+            "test is never defined"
+        );
+
+        let graph = parse_graph(EXAMPLE);
+        assert_eq!(Scheduler::new(&graph).workers(0).run(), Err(vec![GraphError::NoWorkers]));
+
+        let graph = parse_graph(&(EXAMPLE.to_string() + "\nStep X must be finished before step Y can begin."));
+        assert_eq!(graph.check(), Ok(()));
+        assert_eq!(graph.components(), vec![vec![0, 1, 2, 3, 4, 5], vec![6, 7]]);
+    }
+
+    #[test]
+    fn report_test() {
+        let graph = parse_graph(EXAMPLE);
+        let schedule = Scheduler::new(&graph)
+            .workers(2)
+            .durations(scheduler::letter_time(0))
+            .run()
+            .unwrap();
+
+        let table = report::table(&graph, &schedule, 2);
+        let rows: Vec<&str> = table.lines().collect();
+        assert_eq!(rows.len(), 17);
+        assert_eq!(rows[0], "Second   Worker 1   Worker 2   Done");
+        assert_eq!(rows[1].split_whitespace().collect::<Vec<&str>>(), vec!["0", "C", "."]);
+        assert_eq!(rows[4].split_whitespace().collect::<Vec<&str>>(), vec!["3", "A", "F", "C"]);
+        assert_eq!(rows[15].split_whitespace().collect::<Vec<&str>>(), vec!["14", "E", ".", "CABFD"]);
+        assert_eq!(rows[16].split_whitespace().collect::<Vec<&str>>(), vec!["15", ".", ".", "CABFDE"]);
+
+        let (path, length) = report::critical_path(&graph, &schedule);
+        let names: Vec<&str> = path.iter().map(|&t| graph.name(t)).collect();
+        assert_eq!((names, length), (vec!["C", "F", "E"], 14));
+
+        let slack = report::slack_table(&graph, &schedule);
+        assert!(slack.starts_with("Critical path: C -> F -> E (14 seconds, schedule takes 15)"));
+        assert!(slack.lines().any(|l| l.split_whitespace().collect::<Vec<&str>>() == vec!["B", "4", "6", "3"]));
+
+        let svg = report::svg(&graph, &schedule, 2, 10);
+        assert_eq!(svg.matches("<rect x=").count(), 6);
+        assert_eq!(svg.matches("stroke=\"red\"").count(), 3);
+    }
+
+    #[test]
+    fn sweep_test() {
+        let graph = parse_graph(EXAMPLE);
+        let sweep = report::sweep(&graph, 3, &[0, 60]);
+        let rows: Vec<Vec<&str>> = sweep.lines().map(|l| l.split_whitespace().collect()).collect();
+
+        assert_eq!(rows[0], vec!["Base", "1", "2", "3", "Enough"]);
+        assert_eq!(rows[1], vec!["0", "21", "15", "14", "3"]);
+        assert_eq!(rows[2], vec!["60", "381", "258", "253", "3"]);
+        assert_eq!(report::sweep(&graph, 0, &[0, 60]), format!("{:<6}  Enough", "Base"));
+    }
 }
 fn main() {
     let args: Vec<String> = env::args().collect();
+    let (flags, paths): (Vec<String>, Vec<String>) =
+        args.iter().skip(1).cloned().partition(|arg| arg.starts_with("--"));
     let year = "2018".to_string();
     let day = "7".to_string();
 
     let root = env::current_dir().unwrap();
-    let path_str = if args.len() > 1 {
-        args[1].clone()
+    let path_str = if !paths.is_empty() {
+        paths[0].clone()
     } else if root.ends_with(format!("rust_{}_{}", year, day)) {
         format!("../../../Inputs/{}_{}.txt", year, day)
     } else {
@@ -139,9 +239,43 @@ fn main() {
     };
 
 
-    let contents = fs::read_to_string(if args.len() > 1 {path_str} else {RelativePath::new(&path_str).to_path(&root).display().to_string()})
+    let contents = fs::read_to_string(if !paths.is_empty() {path_str} else {RelativePath::new(&path_str).to_path(&root).display().to_string()})
         .expect("Should have been able to read the file");
 
+    let graph = parse_graph(&contents);
+    if let Err(errors) = graph.check() {
+        println!("\nInvalid step graph:");
+        for error in errors {
+            println!("{}", error);
+        }
+        return;
+    }
+
+    let components = graph.components();
+    if components.len() > 1 {
+        println!("\nSteps form {} independent groups:", components.len());
+        for component in components {
+            let names: Vec<&str> = component.iter().map(|&t| graph.name(t)).collect();
+            println!("{}", names.join(" "));
+        }
+    }
+
+    let setting = |name: &str, default: i64| {
+        flags
+            .iter()
+            .find_map(|flag| flag.strip_prefix(name))
+            .map_or(default, |n| n.parse::<i64>().expect("Should have been a number"))
+    };
+    let num_workers = flags
+        .iter()
+        .find_map(|flag| flag.strip_prefix("--workers="))
+        .map_or(5, |n| n.parse::<usize>().expect("Should have been a number of workers"));
+    let base_time = setting("--base-time=", 60);
+    if num_workers == 0 {
+        println!("\nInvalid worker count:\nthere has to be at least one worker");
+        return;
+    }
+
     let part1_timer = Instant::now();
     println!(
         "\nPart 1:\nOrder of completion: {}\nRan in {:.5?}",
@@ -152,7 +286,51 @@ fn main() {
     let part2_timer = Instant::now();
     println!(
         "\nPart 2:\nTime to complete: {}\nRan in {:.5?}",
-        part2(contents.clone(), 5, 60),
+        part2(contents.clone(), num_workers, base_time),
         part2_timer.elapsed()
     );
-}
\ No newline at end of file
+
+    let schedule = Scheduler::new(&graph)
+        .workers(num_workers)
+        .durations(scheduler::letter_time(base_time))
+        .run()
+        .unwrap();
+
+    for flag in &flags {
+        match flag.split_once('=') {
+            Some(("--tie-break", name)) => {
+                let tie_break = scheduler::tie_break(name).expect("Should have been lexicographic, dependents or input");
+                let schedule = Scheduler::new(&graph).tie_break(tie_break).run().unwrap();
+                let order = order_string(&graph, &schedule);
+                println!("\nOrder of completion breaking ties by {}: {}", name, order);
+            }
+            Some(("--durations", path)) => {
+                let table = parse_durations(&fs::read_to_string(path).expect("Should have been able to read the file"));
+                let base = scheduler::letter_time(base_time);
+                let schedule = Scheduler::new(&graph)
+                    .workers(num_workers)
+                    .durations(|name| table.get(name).copied().unwrap_or_else(|| base(name)))
+                    .run()
+                    .unwrap();
+                println!("\nTime to complete with durations from {}: {}", path, scheduler::finish_time(&schedule));
+            }
+            Some(("--svg", path)) => {
+                fs::write(path, report::svg(&graph, &schedule, num_workers, 4)).expect("Should have been able to write the file");
+                println!("\nWrote Gantt chart to {}", path);
+            }
+            Some(("--sweep", max_workers)) => {
+                let max_workers = max_workers.parse::<usize>().expect("Should have been a number");
+                let mut base_times: Vec<i64> = match flags.iter().find_map(|f| f.strip_prefix("--sweep-bases=")) {
+                    Some(list) => list.split(',').map(|n| n.parse().expect("Should have been a number")).collect(),
+                    None => (0..=4).map(|quarter| base_time * quarter / 4).collect(),
+                };
+                base_times.dedup();
+                println!("\nTime to complete by base time and worker count:\n{}", report::sweep(&graph, max_workers, &base_times));
+            }
+            Some(("--workers", _)) | Some(("--base-time", _)) | Some(("--sweep-bases", _)) => {}
+            _ if flag == "--gantt" => println!("\n{}", report::table(&graph, &schedule, num_workers)),
+            _ if flag == "--critical" => println!("\n{}", report::slack_table(&graph, &schedule)),
+            _ => panic!("Unknown option {}", flag),
+        }
+    }
+}
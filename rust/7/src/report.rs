@@ -0,0 +1,208 @@
+use crate::scheduler::{self, Assignment, Graph, Scheduler};
+
+fn join_names(names: &[&str]) -> String {
+    names.join(if names.iter().all(|n| n.len() == 1) { "" } else { " " })
+}
+
+// The puzzle's table: what each worker is on during every second, and the
+// steps done by then in the order they finished, up to the second the last
+// one is done
+pub fn table(graph: &Graph, schedule: &[Assignment], workers: usize) -> String {
+    let finish = schedule.iter().map(|a| a.end).max().unwrap_or(0);
+    let name_width = schedule.iter().map(|a| graph.name(a.task).len()).max().unwrap_or(1);
+    let second_width = finish.to_string().len().max(6);
+
+    let mut done: Vec<&Assignment> = schedule.iter().collect();
+    done.sort_by_key(|a| a.end);
+
+    let mut columns: Vec<String> = (1..=workers).map(|w| format!("Worker {}", w)).collect();
+    let column_width = columns.iter().map(|c| c.len()).max().unwrap_or(0).max(name_width);
+    columns.iter_mut().for_each(|c| *c = format!("{:^w$}", c, w = column_width));
+    let mut table = format!("{:<w$}   {}   Done", "Second", columns.join("   "), w = second_width);
+
+    for second in 0..=finish {
+        let cells: Vec<String> = (0..workers)
+            .map(|w| {
+                let task = schedule
+                    .iter()
+                    .find(|a| a.worker == w && a.start <= second && second < a.end)
+                    .map_or(".", |a| graph.name(a.task));
+                format!("{:^w$}", task, w = column_width)
+            })
+            .collect();
+        let finished: Vec<&str> = done
+            .iter()
+            .take_while(|a| a.end <= second)
+            .map(|a| graph.name(a.task))
+            .collect();
+
+        table += &format!("\n{:>w$}   {}   {}", second, cells.join("   "), join_names(&finished), w = second_width);
+    }
+
+    table
+}
+
+// Each task's duration and its earliest and latest start when only the
+// dependencies hold it back. Tasks are taken in schedule order, which always
+// has prerequisites first.
+fn timings(graph: &Graph, schedule: &[Assignment]) -> (Vec<i64>, Vec<i64>, Vec<i64>) {
+    let mut duration = vec![0; graph.len()];
+    let mut earliest = vec![0; graph.len()];
+    for a in schedule {
+        duration[a.task] = a.end - a.start;
+    }
+    for a in schedule {
+        for &after in graph.next(a.task) {
+            earliest[after] = earliest[after].max(earliest[a.task] + duration[a.task]);
+        }
+    }
+
+    let length = (0..graph.len()).map(|t| earliest[t] + duration[t]).max().unwrap_or(0);
+    let mut latest = vec![0; graph.len()];
+    for a in schedule.iter().rev() {
+        let latest_end = graph.next(a.task).iter().map(|&n| latest[n]).min().unwrap_or(length);
+        latest[a.task] = latest_end - duration[a.task];
+    }
+
+    (duration, earliest, latest)
+}
+
+// The longest chain of dependencies, which no number of workers can beat.
+// Along it no task has slack and each starts the moment the last one ends.
+pub fn critical_path(graph: &Graph, schedule: &[Assignment]) -> (Vec<usize>, i64) {
+    let (duration, earliest, latest) = timings(graph, schedule);
+    let critical = |t: usize, start: i64| earliest[t] == latest[t] && earliest[t] == start;
+
+    let mut path: Vec<usize> = Vec::new();
+    let mut current = (0..graph.len()).filter(|&t| critical(t, 0)).min_by_key(|&t| graph.name(t));
+    while let Some(task) = current {
+        path.push(task);
+        let end = earliest[task] + duration[task];
+        current = graph
+            .next(task)
+            .iter()
+            .cloned()
+            .filter(|&n| critical(n, end))
+            .min_by_key(|&n| graph.name(n));
+    }
+
+    let length = path.iter().map(|&t| duration[t]).sum();
+    (path, length)
+}
+
+// Slack is how long after its earliest start a task can begin without
+// pushing back the end of the longest chain through it
+pub fn slack_table(graph: &Graph, schedule: &[Assignment]) -> String {
+    let (path, length) = critical_path(graph, schedule);
+    let names: Vec<&str> = path.iter().map(|&t| graph.name(t)).collect();
+    let width = schedule.iter().map(|a| graph.name(a.task).len()).max().unwrap_or(0).max(4);
+
+    let mut table = format!(
+        "Critical path: {} ({} seconds, schedule takes {})\n{:<w$}  Start  End    Slack",
+        names.join(" -> "),
+        length,
+        schedule.iter().map(|a| a.end).max().unwrap_or(0),
+        "Step",
+        w = width
+    );
+    let (_, earliest, latest) = timings(graph, schedule);
+    for a in schedule {
+        let slack = latest[a.task] - earliest[a.task];
+        table += &format!("\n{:<w$}  {:<7}{:<7}{}", graph.name(a.task), a.start, a.end, slack, w = width);
+    }
+
+    table
+}
+
+// Completion time for every worker count up to the maximum at each base
+// time, and the fewest workers that reach the best time. Past that point the
+// dependencies, not the workers, hold things up. With no workers to try
+// there's only the header.
+pub fn sweep(graph: &Graph, max_workers: usize, base_times: &[i64]) -> String {
+    let mut table = format!("{:<6}", "Base");
+    for workers in 1..=max_workers {
+        table += &format!("{:>8}", workers);
+    }
+    table += "  Enough";
+    if max_workers == 0 {
+        return table;
+    }
+
+    for &base in base_times {
+        let times: Vec<i64> = (1..=max_workers)
+            .map(|workers| {
+                let schedule = Scheduler::new(graph)
+                    .workers(workers)
+                    .durations(scheduler::letter_time(base))
+                    .run()
+                    .unwrap();
+                scheduler::finish_time(&schedule)
+            })
+            .collect();
+        let best = *times.iter().min().unwrap();
+        let enough = times.iter().position(|&t| t == best).unwrap() + 1;
+
+        table += &format!("\n{:<6}", base);
+        for time in &times {
+            table += &format!("{:>8}", time);
+        }
+        table += &format!("  {}", enough);
+    }
+
+    table
+}
+
+const ROW_HEIGHT: i64 = 28;
+const LABEL_WIDTH: i64 = 80;
+
+// One row of bars per worker; tasks on the critical path are outlined in red
+pub fn svg(graph: &Graph, schedule: &[Assignment], workers: usize, scale: i64) -> String {
+    let finish = schedule.iter().map(|a| a.end).max().unwrap_or(0);
+    let (path, _) = critical_path(graph, schedule);
+    let width = LABEL_WIDTH + finish * scale + 20;
+    let height = ROW_HEIGHT * (workers as i64 + 1) + 10;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"monospace\" font-size=\"12\">\n",
+        width, height
+    );
+    svg += &format!("<rect width=\"{}\" height=\"{}\" fill=\"white\"/>\n", width, height);
+
+    for w in 0..workers {
+        let y = w as i64 * ROW_HEIGHT + 5;
+        svg += &format!("<text x=\"5\" y=\"{}\">Worker {}</text>\n", y + ROW_HEIGHT / 2 + 4, w + 1);
+    }
+
+    for a in schedule {
+        let x = LABEL_WIDTH + a.start * scale;
+        let y = a.worker as i64 * ROW_HEIGHT + 5;
+        let hue = (a.task * 137) % 360;
+        let stroke = if path.contains(&a.task) { "red" } else { "black" };
+        svg += &format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"hsl({}, 60%, 70%)\" stroke=\"{}\"><title>{}: {}-{}</title></rect>\n",
+            x,
+            y,
+            (a.end - a.start) * scale,
+            ROW_HEIGHT - 6,
+            hue,
+            stroke,
+            graph.name(a.task),
+            a.start,
+            a.end
+        );
+        svg += &format!(
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+            x + (a.end - a.start) * scale / 2,
+            y + ROW_HEIGHT / 2 + 1,
+            graph.name(a.task)
+        );
+    }
+
+    let axis = workers as i64 * ROW_HEIGHT + 5;
+    let tick = (finish / 10).max(1);
+    for t in (0..=finish).step_by(tick as usize) {
+        svg += &format!("<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n", LABEL_WIDTH + t * scale, axis + 16, t);
+    }
+
+    svg + "</svg>\n"
+}
@@ -0,0 +1,330 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+// Picks between two available tasks; Less means the first one starts sooner
+pub type TieBreak = fn(&Graph, usize, usize) -> Ordering;
+
+pub fn lexicographic(graph: &Graph, a: usize, b: usize) -> Ordering {
+    graph.name(a).cmp(graph.name(b))
+}
+
+// Tasks that more others directly wait on go first
+pub fn most_dependents(graph: &Graph, a: usize, b: usize) -> Ordering {
+    graph.next[b]
+        .len()
+        .cmp(&graph.next[a].len())
+        .then_with(|| lexicographic(graph, a, b))
+}
+
+// Whichever task was mentioned first in the input
+pub fn input_order(_: &Graph, a: usize, b: usize) -> Ordering {
+    a.cmp(&b)
+}
+
+pub fn tie_break(name: &str) -> Option<TieBreak> {
+    match name {
+        "lexicographic" => Some(lexicographic),
+        "dependents" => Some(most_dependents),
+        "input" => Some(input_order),
+        _ => None,
+    }
+}
+
+// The puzzle's step times: the base plus each letter's place in the alphabet
+pub fn letter_time(base: i64) -> impl Fn(&str) -> i64 {
+    move |name: &str| {
+        base + name
+            .chars()
+            .filter(|c| c.is_ascii_alphabetic())
+            .map(|c| c.to_ascii_uppercase() as i64 - 'A' as i64 + 1)
+            .sum::<i64>()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphError {
+    // The tasks around the cycle, starting from the alphabetically first
+    Cycle(Vec<String>),
+    Undefined { task: String, needed_by: Vec<String> },
+    NoWorkers,
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GraphError::Cycle(tasks) => write!(f, "cycle: {} -> {}", tasks.join(" -> "), tasks[0]),
+            GraphError::Undefined { task, needed_by } if needed_by.is_empty() => write!(f, "{} is never defined", task),
+            GraphError::Undefined { task, needed_by } => {
+                write!(f, "{} is needed by {} but never defined", task, needed_by.join(", "))
+            }
+            GraphError::NoWorkers => write!(f, "there has to be at least one worker"),
+        }
+    }
+}
+
+// Tasks are numbered in the order they're first mentioned. A task that's
+// mentioned but never defined is undefined, and the graph can't be run.
+#[derive(Debug, Default, Clone)]
+pub struct Graph {
+    names: Vec<String>,
+    index: HashMap<String, usize>,
+    defined: Vec<bool>,
+    next: Vec<Vec<usize>>,
+    prereqs: Vec<Vec<usize>>,
+}
+
+impl Graph {
+    pub fn new() -> Graph {
+        Graph::default()
+    }
+
+    fn task(&mut self, name: &str) -> usize {
+        if let Some(&task) = self.index.get(name) {
+            return task;
+        }
+
+        let task = self.names.len();
+        self.names.push(name.to_string());
+        self.index.insert(name.to_string(), task);
+        self.defined.push(false);
+        self.next.push(Vec::new());
+        self.prereqs.push(Vec::new());
+        task
+    }
+
+    pub fn define(&mut self, name: &str) -> usize {
+        let task = self.task(name);
+        self.defined[task] = true;
+        task
+    }
+
+    pub fn depend(&mut self, before: &str, after: &str) {
+        let (before, after) = (self.task(before), self.task(after));
+        if !self.next[before].contains(&after) {
+            self.next[before].push(after);
+            self.prereqs[after].push(before);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn name(&self, task: usize) -> &str {
+        &self.names[task]
+    }
+
+    pub fn next(&self, task: usize) -> &[usize] {
+        &self.next[task]
+    }
+
+    // Every undefined task and one cycle through each knot of tasks that wait
+    // on each other
+    pub fn check(&self) -> Result<(), Vec<GraphError>> {
+        let mut errors: Vec<GraphError> = (0..self.len())
+            .filter(|&t| !self.defined[t])
+            .map(|t| GraphError::Undefined {
+                task: self.names[t].clone(),
+                needed_by: self.next[t].iter().map(|&n| self.names[n].clone()).collect(),
+            })
+            .collect();
+        errors.extend(self.cycles().into_iter().map(GraphError::Cycle));
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    // Iterative DFS. A back edge closes a cycle along the current path; it's
+    // only reported if none of its tasks are in a cycle already reported.
+    fn cycles(&self) -> Vec<Vec<String>> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum State {
+            New,
+            OnPath,
+            Done,
+        }
+
+        let mut state = vec![State::New; self.len()];
+        let mut in_cycle = vec![false; self.len()];
+        let mut cycles: Vec<Vec<String>> = Vec::new();
+
+        for root in 0..self.len() {
+            if state[root] != State::New {
+                continue;
+            }
+
+            let mut path: Vec<(usize, usize)> = vec![(root, 0)];
+            state[root] = State::OnPath;
+            while let Some(&(task, edge)) = path.last() {
+                let Some(&next) = self.next[task].get(edge) else {
+                    state[task] = State::Done;
+                    path.pop();
+                    continue;
+                };
+                path.last_mut().unwrap().1 += 1;
+
+                match state[next] {
+                    State::New => {
+                        state[next] = State::OnPath;
+                        path.push((next, 0));
+                    }
+                    State::OnPath => {
+                        let start = path.iter().position(|&(t, _)| t == next).unwrap();
+                        let mut cycle: Vec<usize> = path[start..].iter().map(|&(t, _)| t).collect();
+                        if cycle.iter().any(|&t| in_cycle[t]) {
+                            continue;
+                        }
+
+                        for &t in &cycle {
+                            in_cycle[t] = true;
+                        }
+                        let first = (0..cycle.len()).min_by_key(|&i| &self.names[cycle[i]]).unwrap();
+                        cycle.rotate_left(first);
+                        cycles.push(cycle.iter().map(|&t| self.names[t].clone()).collect());
+                    }
+                    State::Done => {}
+                }
+            }
+        }
+
+        cycles
+    }
+
+    // Groups of tasks with no dependencies between groups, each in input order
+    pub fn components(&self) -> Vec<Vec<usize>> {
+        let mut component: Vec<Option<usize>> = vec![None; self.len()];
+        let mut components: Vec<Vec<usize>> = Vec::new();
+
+        for root in 0..self.len() {
+            if component[root].is_some() {
+                continue;
+            }
+
+            let mut members: Vec<usize> = Vec::new();
+            let mut stack: Vec<usize> = vec![root];
+            component[root] = Some(components.len());
+            while let Some(task) = stack.pop() {
+                members.push(task);
+                for &other in self.next[task].iter().chain(&self.prereqs[task]) {
+                    if component[other].is_none() {
+                        component[other] = Some(components.len());
+                        stack.push(other);
+                    }
+                }
+            }
+
+            members.sort();
+            components.push(members);
+        }
+
+        components
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Assignment {
+    pub task: usize,
+    pub worker: usize,
+    pub start: i64,
+    pub end: i64,
+}
+
+pub struct Scheduler<'a> {
+    graph: &'a Graph,
+    workers: usize,
+    duration: Box<dyn Fn(&str) -> i64 + 'a>,
+    tie_break: TieBreak,
+}
+
+impl<'a> Scheduler<'a> {
+    // One worker, tasks that take no time and lexicographic tie-breaking,
+    // which gives the plain topological order
+    pub fn new(graph: &'a Graph) -> Scheduler<'a> {
+        Scheduler {
+            graph,
+            workers: 1,
+            duration: Box::new(|_| 0),
+            tie_break: lexicographic,
+        }
+    }
+
+    pub fn workers(mut self, workers: usize) -> Scheduler<'a> {
+        self.workers = workers;
+        self
+    }
+
+    pub fn durations(mut self, duration: impl Fn(&str) -> i64 + 'a) -> Scheduler<'a> {
+        self.duration = Box::new(duration);
+        self
+    }
+
+    pub fn tie_break(mut self, tie_break: TieBreak) -> Scheduler<'a> {
+        self.tie_break = tie_break;
+        self
+    }
+
+    // Every task, in the order they were started. Whenever time moves on,
+    // everything finishing at that moment is done before idle workers pick up
+    // the newly available tasks. Graphs that fail their check aren't run, as
+    // some tasks could never start, and neither is anything without workers.
+    pub fn run(&self) -> Result<Vec<Assignment>, Vec<GraphError>> {
+        let graph = self.graph;
+        if self.workers == 0 {
+            return Err(vec![GraphError::NoWorkers]);
+        }
+        graph.check()?;
+
+        let mut waiting: Vec<usize> = graph.prereqs.iter().map(|p| p.len()).collect();
+        let mut available: Vec<usize> = (0..graph.len()).filter(|&t| waiting[t] == 0).collect();
+        let mut running: Vec<Assignment> = Vec::new();
+        let mut schedule: Vec<Assignment> = Vec::new();
+        let mut time = 0;
+
+        loop {
+            while running.len() < self.workers && !available.is_empty() {
+                let pick = (0..available.len())
+                    .min_by(|&i, &j| (self.tie_break)(graph, available[i], available[j]))
+                    .unwrap();
+                let task = available.swap_remove(pick);
+                let worker = (0..self.workers)
+                    .find(|&w| running.iter().all(|a| a.worker != w))
+                    .unwrap();
+                let assignment = Assignment {
+                    task,
+                    worker,
+                    start: time,
+                    end: time + (self.duration)(graph.name(task)),
+                };
+                running.push(assignment);
+                schedule.push(assignment);
+            }
+
+            let Some(next) = running.iter().map(|a| a.end).min() else {
+                break;
+            };
+            time = next;
+
+            let (done, still_running): (Vec<Assignment>, Vec<Assignment>) =
+                running.into_iter().partition(|a| a.end == time);
+            running = still_running;
+            for assignment in done {
+                for &after in &graph.next[assignment.task] {
+                    waiting[after] -= 1;
+                    if waiting[after] == 0 {
+                        available.push(after);
+                    }
+                }
+            }
+        }
+
+        Ok(schedule)
+    }
+}
+
+pub fn finish_time(schedule: &[Assignment]) -> i64 {
+    schedule.iter().map(|a| a.end).max().unwrap_or(0)
+}
@@ -0,0 +1,115 @@
+use crate::tree::{Entry, Node};
+use std::fmt;
+
+// A leaf counted so many times its share of the root's value can't be held
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow {
+    pub id: usize,
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{} is counted too many times to weigh", self.id)
+    }
+}
+
+// Nodes are numbered in pre-order, which is also the order their headers
+// appear in the input
+fn describe(id: usize, entry: &Entry) -> String {
+    let metadata: Vec<String> = entry.node.metadata.iter().map(|m| m.to_string()).collect();
+    format!("#{} value {} metadata [{}]", id, entry.value, metadata.join(", "))
+}
+
+pub fn pretty(tree: &Node) -> String {
+    tree.entries()
+        .iter()
+        .enumerate()
+        .map(|(id, entry)| format!("{}{}", "  ".repeat(entry.depth), describe(id, entry)))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub fn stats(tree: &Node) -> String {
+    let entries = tree.entries();
+    let leaves = entries.iter().filter(|e| e.children.is_empty()).count();
+    let branches = entries.len() - leaves;
+    let metadata: usize = entries.iter().map(|e| e.node.metadata.len()).sum();
+
+    format!(
+        "Nodes: {}\nLeaves: {}\nDepth: {}\nMost children: {}\nMean children per branch: {:.2}\nMetadata entries: {}",
+        entries.len(),
+        leaves,
+        entries.iter().map(|e| e.depth).max().unwrap_or(0),
+        entries.iter().map(|e| e.children.len()).max().unwrap_or(0),
+        if branches == 0 { 0.0 } else { (entries.len() - 1) as f64 / branches as f64 },
+        metadata
+    )
+}
+
+// The way down to the leaf that adds the most to the root's value. A node
+// is counted once for every metadata entry pointing at it, and again for
+// each time its parent is, so a leaf adds its value times the product of
+// those counts down its path. Ties go to the leftmost leaf, and a tree worth
+// nothing is just the root.
+pub fn heaviest_path(tree: &Node) -> Result<Vec<usize>, Overflow> {
+    let entries = tree.entries();
+    let mut parents: Vec<usize> = vec![0; entries.len()];
+    // None where the count is past even an i128, which only matters if the
+    // leaf under it is worth something
+    let mut weights: Vec<Option<i128>> = vec![Some(0); entries.len()];
+    weights[0] = Some(1);
+
+    // Parents come before their children in pre-order
+    for (id, entry) in entries.iter().enumerate() {
+        for (k, &child) in entry.children.iter().enumerate() {
+            let refs = entry.node.metadata.iter().filter(|&&m| m == k as i64 + 1).count() as i128;
+            parents[child] = id;
+            weights[child] = if refs == 0 { Some(0) } else { weights[id].and_then(|w| w.checked_mul(refs)) };
+        }
+    }
+
+    let mut heaviest: Option<(i128, usize)> = None;
+    for id in (0..entries.len()).filter(|&id| entries[id].children.is_empty() && entries[id].value != 0) {
+        let contribution = weights[id]
+            .and_then(|w| w.checked_mul(entries[id].value as i128))
+            .ok_or(Overflow { id })?;
+        if contribution > 0 && heaviest.is_none_or(|(best, _)| contribution > best) {
+            heaviest = Some((contribution, id));
+        }
+    }
+
+    let mut path: Vec<usize> = vec![heaviest.map_or(0, |(_, id)| id)];
+    while *path.last().unwrap() != 0 {
+        path.push(parents[*path.last().unwrap()]);
+    }
+    path.reverse();
+
+    Ok(path)
+}
+
+pub fn path_string(tree: &Node, path: &[usize]) -> String {
+    let entries = tree.entries();
+    path.iter()
+        .map(|&id| format!("#{} ({})", id, entries[id].value))
+        .collect::<Vec<String>>()
+        .join(" -> ")
+}
+
+// Graphviz DOT with the heaviest path filled in, if it can be weighed
+pub fn dot(tree: &Node) -> String {
+    let entries = tree.entries();
+    let path = heaviest_path(tree).unwrap_or_default();
+
+    let mut dot = String::from("digraph license {\n    node [shape=box, fontname=\"monospace\"];\n");
+    for (id, entry) in entries.iter().enumerate() {
+        let style = if path.contains(&id) { ", style=filled, fillcolor=\"#f4c542\"" } else { "" };
+        dot += &format!("    n{} [label=\"{}\"{}];\n", id, describe(id, entry), style);
+    }
+    for (id, entry) in entries.iter().enumerate() {
+        for &child in &entry.children {
+            dot += &format!("    n{} -> n{};\n", id, child);
+        }
+    }
+
+    dot + "}\n"
+}
@@ -3,75 +3,17 @@ use std::env;
 use std::fs;
 use std::time::Instant;
 
-fn part1(contents: String) -> i64 {
-    return sum_metadata(
-        &contents
-            .split_whitespace()
-            .map(|x| x.parse().unwrap())
-            .collect(),
-        0,
-    )
-    .0;
-}
-
-fn part2(contents: String) -> i64 {
-    return evaluate(
-        &contents
-            .split_whitespace()
-            .map(|x| x.parse().unwrap())
-            .collect(),
-        0,
-    )
-    .0;
-}
+mod explore;
+mod tree;
 
-fn sum_metadata(nums: &Vec<i64>, index: usize) -> (i64, usize) {
-    let num_children = nums[index];
-    let num_metadata = nums[index + 1];
-
-    let mut sum = 0;
-    let mut i = index + 2;
-    for _ in 0..num_children {
-        let (child_sum, new_i) = sum_metadata(nums, i);
-
-        sum += child_sum;
-        i = new_i;
-    }
-
-    for _ in 0..num_metadata {
-        sum += nums[i];
-        i += 1;
-    }
+use tree::Node;
 
-    return (sum, i);
+fn part1(contents: String) -> i64 {
+    Node::parse(&contents).expect("Should have been a valid license tree").sum_metadata()
 }
 
-fn evaluate(nums: &Vec<i64>, index: usize) -> (i64, usize) {
-    let num_children = nums[index];
-    if num_children == 0 {
-        return sum_metadata(nums, index);
-    }
-
-    let num_metadata = nums[index + 1];
-    let mut children_vals = vec![];
-    let mut i = index + 2;
-
-    for _ in 0..num_children {
-        let (child_val, new_i) = evaluate(nums, i);
-        children_vals.push(child_val);
-        i = new_i;
-    }
-
-    let mut sum = 0;
-    for _ in 0..num_metadata {
-        let metadata = nums[i];
-        if metadata > 0 && metadata <= num_children {
-            sum += children_vals[(metadata - 1) as usize];
-        }
-        i += 1;
-    }
-
-    return (sum, i);
+fn part2(contents: String) -> i64 {
+    Node::parse(&contents).expect("Should have been a valid license tree").value()
 }
 
 #[cfg(test)]
@@ -93,15 +35,110 @@ mod tests {
 
         assert_eq!(part2(contents), 66);
     }
+
+    const EXAMPLE: &str = "2 3 0 3 10 11 12 1 1 0 1 99 2 1 1 2";
+
+    #[test]
+    fn parse_test() {
+        let tree = Node::parse(EXAMPLE).unwrap();
+
+        assert_eq!(tree.metadata, vec![1, 1, 2]);
+        assert_eq!(tree.children[1].children[0].metadata, vec![99]);
+        assert_eq!((tree.sum_metadata(), tree.value()), (138, 66));
+        assert_eq!(tree.serialize(), EXAMPLE);
+        assert_eq!(Node::parse(&tree.serialize()), Ok(tree));
+        assert_eq!(Node::parse("0 0").unwrap().serialize(), "0 0");
+    }
+
+    #[test]
+    fn malformed_test() {
+        use tree::ParseError;
+
+        assert_eq!(Node::parse(""), Err(ParseError::Truncated { offset: 0, wanted: "child count" }));
+        assert_eq!(Node::parse("2 3 0 3 10 11 12 1 1 0 1 99 2 1"), Err(ParseError::Truncated { offset: 14, wanted: "metadata entry" }));
+        assert_eq!(Node::parse("1 1 0"), Err(ParseError::Truncated { offset: 3, wanted: "metadata count" }));
+        assert_eq!(Node::parse("1 1 0 x 5"), Err(ParseError::BadNumber { offset: 3, text: "x".to_string() }));
+        assert_eq!(Node::parse("-1 0"), Err(ParseError::BadNumber { offset: 0, text: "-1".to_string() }));
+        assert_eq!(Node::parse("0 1 5 0 0"), Err(ParseError::Trailing { offset: 3 }));
+        assert_eq!(Node::parse("0 99999999999999999"), Err(ParseError::Truncated { offset: 2, wanted: "metadata entry" }));
+        assert_eq!(Node::parse("99999999999999999 0 0 0"), Err(ParseError::Truncated { offset: 4, wanted: "child count" }));
+        assert_eq!(
+            ParseError::Truncated { offset: 14, wanted: "metadata entry" }.to_string(),
+            "offset 14: input ends where a metadata entry should be"
+        );
+    }
+
+    #[test]
+    fn deep_tree_test() {
+        let depth = 500_000;
+        let flat = "1 1 ".repeat(depth) + "0 1 7" + &" 1".repeat(depth);
+        let tree = Node::parse(&flat).unwrap();
+
+        assert_eq!(tree.sum_metadata(), 7 + depth as i64);
+        assert_eq!(tree.value(), 7);
+        assert_eq!(tree.serialize(), flat);
+
+        let copy = tree.clone();
+        assert!(copy == tree);
+        assert!(Node::parse(&flat.replacen("0 1 7", "0 1 8", 1)).unwrap() != tree);
+        assert_eq!(format!("{:?}", Node::parse("0 1 7").unwrap()), "Node(0 1 7)");
+    }
+
+    #[test]
+    fn explore_test() {
+        let tree = Node::parse(EXAMPLE).unwrap();
+
+        assert_eq!(
+            explore::pretty(&tree),
+            "#0 value 66 metadata [1, 1, 2]
+  #1 value 33 metadata [10, 11, 12]
+  #2 value 0 metadata [2]
+    #3 value 99 metadata [99]"
+        );
+        assert_eq!(
+            explore::stats(&tree),
+            "Nodes: 4\nLeaves: 2\nDepth: 2\nMost children: 2\nMean children per branch: 1.50\nMetadata entries: 8"
+        );
+        assert_eq!(explore::heaviest_path(&tree), Ok(vec![0, 1]));
+        assert_eq!(explore::path_string(&tree, &[0, 1]), "#0 (66) -> #1 (33)");
+
+        // Following the biggest child at each level would end at a leaf
+        // adding 5, under a child adding 10
+        let lopsided = Node::parse("2 2 2 2 0 1 5 0 1 5 1 2 1 1 0 1 9 1 1 2").unwrap();
+        assert_eq!(explore::heaviest_path(&lopsided), Ok(vec![0, 4, 5]));
+        assert_eq!(explore::heaviest_path(&Node::parse("1 1 0 1 5 2").unwrap()), Ok(vec![0]));
+
+        // 40 levels each pointing ten times at the next, with a sibling
+        // cancelling out all but 5 of that so the values stay small, leaves
+        // the bottom leaf counted 10^40 times
+        let chain = |bottom: i64, cancel: i64| {
+            let mut tree = format!("0 1 {}", bottom);
+            for _ in 0..40 {
+                tree = format!("2 11 {} 0 1 {} 1 1 1 1 1 1 1 1 1 1 2", tree, cancel);
+            }
+            tree
+        };
+        let deep = Node::parse(&chain(5, -45)).unwrap();
+        assert_eq!(deep.value(), 5);
+        assert_eq!(explore::heaviest_path(&deep), Err(explore::Overflow { id: 40 }));
+        let worthless = Node::parse(&format!("2 1 {} 0 1 7 2", chain(0, 0))).unwrap();
+        assert_eq!(explore::heaviest_path(&worthless), Ok(vec![0, 82]));
+
+        let dot = explore::dot(&tree);
+        assert!(dot.contains("    n2 -> n3;\n"));
+        assert_eq!(dot.matches("style=filled").count(), 2);
+    }
 }
 fn main() {
     let args: Vec<String> = env::args().collect();
+    let (flags, paths): (Vec<String>, Vec<String>) =
+        args.iter().skip(1).cloned().partition(|arg| arg.starts_with("--"));
     let year = "2018".to_string();
     let day = "8".to_string();
 
     let root = env::current_dir().unwrap();
-    let path_str = if args.len() > 1 {
-        args[1].clone()
+    let path_str = if !paths.is_empty() {
+        paths[0].clone()
     } else if root.ends_with(format!("rust_{}_{}", year, day)) {
         format!("../../../Inputs/{}_{}.txt", year, day)
     } else {
@@ -109,9 +146,17 @@ fn main() {
     };
 
 
-    let contents = fs::read_to_string(if args.len() > 1 {path_str} else {RelativePath::new(&path_str).to_path(&root).display().to_string()})
+    let contents = fs::read_to_string(if !paths.is_empty() {path_str} else {RelativePath::new(&path_str).to_path(&root).display().to_string()})
         .expect("Should have been able to read the file");
 
+    let tree = match Node::parse(&contents) {
+        Ok(tree) => tree,
+        Err(error) => {
+            println!("\nInvalid license file:\n{}", error);
+            return;
+        }
+    };
+
     let part1_timer = Instant::now();
     println!(
         "\nPart 1:\nSum of metadata entries: {}\nRan in {:.5?}",
@@ -125,4 +170,21 @@ fn main() {
         part2(contents.clone()),
         part2_timer.elapsed()
     );
-}
\ No newline at end of file
+
+    for flag in &flags {
+        match flag.split_once('=') {
+            _ if flag == "--serialize" => println!("\n{}", tree.serialize()),
+            _ if flag == "--pretty" => println!("\n{}", explore::pretty(&tree)),
+            _ if flag == "--stats" => println!("\n{}", explore::stats(&tree)),
+            _ if flag == "--path" => match explore::heaviest_path(&tree) {
+                Ok(path) => println!("\nHeaviest path: {}", explore::path_string(&tree, &path)),
+                Err(error) => println!("\nNo heaviest path:\n{}", error),
+            },
+            Some(("--dot", path)) => {
+                fs::write(path, explore::dot(&tree)).expect("Should have been able to write the file");
+                println!("\nWrote tree to {}", path);
+            }
+            _ => panic!("Unknown option {}", flag),
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    // Offsets count numbers from the start of the input, not characters
+    BadNumber { offset: usize, text: String },
+    Truncated { offset: usize, wanted: &'static str },
+    Trailing { offset: usize },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::BadNumber { offset, text } => write!(f, "offset {}: \"{}\" isn't a valid number here", offset, text),
+            ParseError::Truncated { offset, wanted } => write!(f, "offset {}: input ends where a {} should be", offset, wanted),
+            ParseError::Trailing { offset } => write!(f, "offset {}: numbers left over after the root node", offset),
+        }
+    }
+}
+
+// Clone, equality and Debug are written out below rather than derived, as
+// the derived ones recurse once per level
+#[derive(Default)]
+pub struct Node {
+    pub children: Vec<Node>,
+    pub metadata: Vec<i64>,
+}
+
+// A node still being read: its header, and the children read so far
+struct Partial {
+    children_left: usize,
+    metadata: usize,
+    children: Vec<Node>,
+}
+
+impl Node {
+    // Reads the flat header/children/metadata list with an explicit stack, so
+    // the depth of the tree doesn't matter
+    pub fn parse(contents: &str) -> Result<Node, ParseError> {
+        let tokens: Vec<&str> = contents.split_whitespace().collect();
+        let mut offset = 0;
+        let mut next = |wanted: &'static str| -> Result<(usize, &str), ParseError> {
+            let token = *tokens.get(offset).ok_or(ParseError::Truncated { offset, wanted })?;
+            offset += 1;
+            Ok((offset - 1, token))
+        };
+        let count = |(offset, text): (usize, &str)| {
+            text.parse::<usize>().map_err(|_| ParseError::BadNumber {
+                offset,
+                text: text.to_string(),
+            })
+        };
+
+        let mut stack: Vec<Partial> = Vec::new();
+        let root = loop {
+            let children_left = count(next("child count")?)?;
+            let metadata = count(next("metadata count")?)?;
+            stack.push(Partial {
+                children_left,
+                metadata,
+                children: Vec::new(),
+            });
+
+            // Close every node whose children are all read
+            let mut done: Option<Node> = None;
+            while let Some(top) = stack.last_mut() {
+                if let Some(child) = done.take() {
+                    top.children.push(child);
+                    top.children_left -= 1;
+                }
+                if top.children_left > 0 {
+                    break;
+                }
+
+                // Counts come straight from the input, so nothing is sized by
+                // them up front; a count too big for the input runs out of
+                // numbers like any other truncated one
+                let top = stack.pop().unwrap();
+                let mut metadata: Vec<i64> = Vec::new();
+                for _ in 0..top.metadata {
+                    let (offset, text) = next("metadata entry")?;
+                    metadata.push(text.parse().map_err(|_| ParseError::BadNumber {
+                        offset,
+                        text: text.to_string(),
+                    })?);
+                }
+                done = Some(Node {
+                    children: top.children,
+                    metadata,
+                });
+            }
+
+            if stack.is_empty() {
+                break done.unwrap();
+            }
+        };
+
+        if offset < tokens.len() {
+            return Err(ParseError::Trailing { offset });
+        }
+
+        Ok(root)
+    }
+
+    // Back to the flat format, single spaced
+    pub fn serialize(&self) -> String {
+        let mut nums: Vec<String> = Vec::new();
+        let mut stack: Vec<(&Node, bool)> = vec![(self, false)];
+        while let Some((node, expanded)) = stack.pop() {
+            if expanded {
+                nums.extend(node.metadata.iter().map(|m| m.to_string()));
+            } else {
+                nums.push(node.children.len().to_string());
+                nums.push(node.metadata.len().to_string());
+                stack.push((node, true));
+                stack.extend(node.children.iter().rev().map(|c| (c, false)));
+            }
+        }
+
+        nums.join(" ")
+    }
+
+    pub fn sum_metadata(&self) -> i64 {
+        let mut sum = 0;
+        let mut stack: Vec<&Node> = vec![self];
+        while let Some(node) = stack.pop() {
+            sum += node.metadata.iter().sum::<i64>();
+            stack.extend(&node.children);
+        }
+
+        sum
+    }
+
+    // A leaf is worth its metadata total, anything else the total of the
+    // children its metadata points at (1 based, out of range ones skipped)
+    fn own_value(&self, child_values: &[i64]) -> i64 {
+        if self.children.is_empty() {
+            return self.metadata.iter().sum();
+        }
+
+        self.metadata
+            .iter()
+            .filter(|&&m| m >= 1 && m as usize <= child_values.len())
+            .map(|&m| child_values[m as usize - 1])
+            .sum()
+    }
+
+    // Every node in pre-order, so the root comes first and children follow
+    // their parent, each with its depth, where its children are and its value
+    pub fn entries(&self) -> Vec<Entry<'_>> {
+        let mut entries: Vec<Entry> = Vec::new();
+        let mut stack: Vec<(&Node, usize, Option<usize>)> = vec![(self, 0, None)];
+        while let Some((node, depth, parent)) = stack.pop() {
+            let id = entries.len();
+            if let Some(parent) = parent {
+                entries[parent].children.push(id);
+            }
+            stack.extend(node.children.iter().rev().map(|c| (c, depth + 1, Some(id))));
+            entries.push(Entry {
+                node,
+                depth,
+                children: Vec::new(),
+                value: 0,
+            });
+        }
+
+        // Backwards, every node's children are valued before it is
+        for i in (0..entries.len()).rev() {
+            let child_values: Vec<i64> = entries[i].children.iter().map(|&c| entries[c].value).collect();
+            entries[i].value = entries[i].node.own_value(&child_values);
+        }
+
+        entries
+    }
+
+    pub fn value(&self) -> i64 {
+        self.entries()[0].value
+    }
+}
+
+pub struct Entry<'a> {
+    pub node: &'a Node,
+    pub depth: usize,
+    pub children: Vec<usize>,
+    pub value: i64,
+}
+
+impl Clone for Node {
+    // Children are built before their parent, so each node takes the last
+    // of the finished nodes as its children
+    fn clone(&self) -> Node {
+        let mut done: Vec<Node> = Vec::new();
+        let mut stack: Vec<(&Node, bool)> = vec![(self, false)];
+        while let Some((node, expanded)) = stack.pop() {
+            if expanded {
+                let children = done.split_off(done.len() - node.children.len());
+                done.push(Node {
+                    children,
+                    metadata: node.metadata.clone(),
+                });
+            } else {
+                stack.push((node, true));
+                stack.extend(node.children.iter().rev().map(|c| (c, false)));
+            }
+        }
+
+        done.pop().unwrap()
+    }
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Node) -> bool {
+        let mut stack: Vec<(&Node, &Node)> = vec![(self, other)];
+        while let Some((a, b)) = stack.pop() {
+            if a.metadata != b.metadata || a.children.len() != b.children.len() {
+                return false;
+            }
+            stack.extend(a.children.iter().zip(&b.children));
+        }
+
+        true
+    }
+}
+
+impl Eq for Node {}
+
+// The flat format, which is built without recursing
+impl fmt::Debug for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Node({})", self.serialize())
+    }
+}
+
+// The default drop recurses once per level, which deep trees can't afford
+impl Drop for Node {
+    fn drop(&mut self) {
+        let mut stack: Vec<Node> = std::mem::take(&mut self.children);
+        while let Some(mut node) = stack.pop() {
+            stack.append(&mut node.children);
+        }
+    }
+}
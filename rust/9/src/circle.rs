@@ -0,0 +1,281 @@
+use std::fmt;
+
+// The circle as a doubly linked list over marble numbers: next[m] is the
+// marble clockwise of m and prev[m] the one counter-clockwise. Every marble
+// that will ever be placed has its slot from the start, so nothing moves or
+// grows while playing.
+#[derive(Debug, Clone)]
+pub struct Circle {
+    next: Vec<u32>,
+    prev: Vec<u32>,
+    current: u32,
+    len: usize,
+}
+
+impl Circle {
+    fn new(last_marble: usize) -> Circle {
+        Circle {
+            next: vec![0; last_marble + 1],
+            prev: vec![0; last_marble + 1],
+            current: 0,
+            len: 1,
+        }
+    }
+
+    pub fn current(&self) -> usize {
+        self.current as usize
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    fn clockwise(&self, marble: u32, steps: usize) -> u32 {
+        (0..steps).fold(marble, |m, _| self.next[m as usize])
+    }
+
+    fn counter_clockwise(&self, marble: u32, steps: usize) -> u32 {
+        (0..steps).fold(marble, |m, _| self.prev[m as usize])
+    }
+
+    // Puts the marble straight after `after` and makes it current
+    fn insert_after(&mut self, after: u32, marble: u32) {
+        let before = self.next[after as usize];
+        self.next[after as usize] = marble;
+        self.prev[marble as usize] = after;
+        self.next[marble as usize] = before;
+        self.prev[before as usize] = marble;
+        self.current = marble;
+        self.len += 1;
+    }
+
+    // Takes the marble out and makes the one clockwise of it current
+    fn remove(&mut self, marble: u32) {
+        let (before, after) = (self.prev[marble as usize], self.next[marble as usize]);
+        self.next[before as usize] = after;
+        self.prev[after as usize] = before;
+        self.current = after;
+        self.len -= 1;
+    }
+
+    // The whole circle clockwise from marble 0, like the puzzle writes it.
+    // Rules can take marble 0 out, in which case it starts from the current
+    // marble instead.
+    pub fn marbles(&self) -> Vec<usize> {
+        let mut marbles: Vec<usize> = Vec::with_capacity(self.len);
+        let mut marble = self.current;
+        for _ in 0..self.len {
+            marbles.push(marble as usize);
+            marble = self.next[marble as usize];
+        }
+        if let Some(zero) = marbles.iter().position(|&m| m == 0) {
+            marbles.rotate_left(zero);
+        }
+
+        marbles
+    }
+
+    // Up to `radius` marbles either side of the current one, clockwise
+    pub fn window(&self, radius: usize) -> Vec<usize> {
+        let radius = radius.min((self.len - 1) / 2);
+        let start = self.counter_clockwise(self.current, radius);
+        let mut marbles: Vec<usize> = Vec::with_capacity(2 * radius + 1);
+        let mut marble = start;
+        for _ in 0..=2 * radius {
+            marbles.push(marble as usize);
+            marble = self.next[marble as usize];
+        }
+
+        marbles
+    }
+}
+
+// Every scoring_multiple'th marble is kept along with the marble
+// remove_back places counter-clockwise of the current one; any other marble
+// goes place_forward places clockwise of it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarbleRules {
+    pub scoring_multiple: usize,
+    pub remove_back: usize,
+    pub place_forward: usize,
+}
+
+impl Default for MarbleRules {
+    fn default() -> MarbleRules {
+        MarbleRules {
+            scoring_multiple: 23,
+            remove_back: 7,
+            place_forward: 2,
+        }
+    }
+}
+
+impl MarbleRules {
+    // "scoring_multiple,remove_back,place_forward", e.g. "23,7,2"
+    pub fn parse(spec: &str) -> Option<MarbleRules> {
+        let nums: Vec<usize> = spec.split(',').map(|n| n.trim().parse().ok()).collect::<Option<Vec<usize>>>()?;
+        match nums[..] {
+            [scoring_multiple, remove_back, place_forward] if scoring_multiple > 0 && place_forward > 0 => Some(MarbleRules {
+                scoring_multiple,
+                remove_back,
+                place_forward,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameError {
+    NoPlayers,
+    // Marbles are numbered in 32 bits
+    TooManyMarbles { last_marble: usize },
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::NoPlayers => write!(f, "there are no players to take turns"),
+            GameError::TooManyMarbles { last_marble } => {
+                write!(f, "last marble {} is past the highest marble, {}", last_marble, u32::MAX)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Game {
+    // Indexed by player number - 1; player 1 places marble 1
+    pub scores: Vec<i64>,
+    pub circle: Circle,
+    rules: MarbleRules,
+}
+
+impl Game {
+    fn new(players: usize, last_marble: usize, rules: MarbleRules) -> Result<Game, GameError> {
+        if players == 0 {
+            return Err(GameError::NoPlayers);
+        }
+        if last_marble > u32::MAX as usize {
+            return Err(GameError::TooManyMarbles { last_marble });
+        }
+
+        Ok(Game {
+            scores: vec![0; players],
+            circle: Circle::new(last_marble),
+            rules,
+        })
+    }
+
+    // Who places the marble; nobody places marble 0
+    pub fn player(&self, marble: usize) -> Option<usize> {
+        (marble > 0).then(|| (marble - 1) % self.scores.len() + 1)
+    }
+
+    // With only marble 0 left there's nothing to take, so the scoring marble
+    // is kept on its own
+    fn turn(&mut self, marble: u32) {
+        let player = (marble as usize - 1) % self.scores.len();
+        let circle = &mut self.circle;
+        if (marble as usize).is_multiple_of(self.rules.scoring_multiple) {
+            self.scores[player] += marble as i64;
+            if circle.len > 1 {
+                let removed = circle.counter_clockwise(circle.current, self.rules.remove_back);
+                circle.remove(removed);
+                self.scores[player] += removed as i64;
+            }
+        } else {
+            let after = circle.clockwise(circle.current, self.rules.place_forward - 1);
+            circle.insert_after(after, marble);
+        }
+    }
+
+    // The top scoring player's number and score, the lowest number on ties
+    pub fn winner(&self) -> (usize, i64) {
+        let (index, score) = self
+            .scores
+            .iter()
+            .enumerate()
+            .max_by_key(|&(i, score)| (*score, std::cmp::Reverse(i)))
+            .unwrap();
+
+        (index + 1, *score)
+    }
+}
+
+pub fn play(players: usize, last_marble: usize, rules: MarbleRules) -> Result<Game, GameError> {
+    let mut game = Game::new(players, last_marble, rules)?;
+    for marble in 1..=last_marble as u32 {
+        game.turn(marble);
+    }
+
+    Ok(game)
+}
+
+// The circle after a turn; player is None before the first one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Turn {
+    pub player: Option<usize>,
+    pub marbles: Vec<usize>,
+    pub current: usize,
+}
+
+// The puzzle's layout: every marble right aligned in three columns, the
+// current one in brackets that take over the neighbouring padding
+impl fmt::Display for Turn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut line = match self.player {
+            Some(player) => format!("[{}]", player),
+            None => "[-]".to_string(),
+        };
+
+        let mut closing = false;
+        for &marble in &self.marbles {
+            let mut slot = if marble == self.current { format!("{:>3}", format!("({}", marble)) } else { format!("{:>3}", marble) };
+            if closing {
+                slot.replace_range(0..1, ")");
+            }
+            line += &slot;
+            closing = marble == self.current;
+        }
+
+        write!(f, "{}{}", line, if closing { ")" } else { " " })
+    }
+}
+
+// Steps through a game one turn at a time, starting with just marble 0
+pub struct Replay {
+    game: Game,
+    next_marble: usize,
+    last_marble: usize,
+}
+
+pub fn replay(players: usize, last_marble: usize, rules: MarbleRules) -> Result<Replay, GameError> {
+    Ok(Replay {
+        game: Game::new(players, last_marble, rules)?,
+        next_marble: 0,
+        last_marble,
+    })
+}
+
+impl Iterator for Replay {
+    type Item = Turn;
+
+    fn next(&mut self) -> Option<Turn> {
+        if self.next_marble > self.last_marble {
+            return None;
+        }
+
+        let marble = self.next_marble;
+        if marble > 0 {
+            self.game.turn(marble as u32);
+        }
+        self.next_marble += 1;
+
+        Some(Turn {
+            player: self.game.player(marble),
+            marbles: self.game.circle.marbles(),
+            current: self.game.circle.current(),
+        })
+    }
+}
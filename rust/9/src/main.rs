@@ -1,61 +1,69 @@
 use relative_path::RelativePath;
-use std::collections::VecDeque;
 use std::env;
+use std::fmt;
 use std::fs;
 use std::time::Instant;
 
-fn part1(contents: String) -> i64 {
-    return contents
-        .lines()
-        .map(|line| {
-            let mut parts = line.split_whitespace();
-            let players = parts.next().unwrap().parse::<i64>().unwrap();
-            let last_marble = parts.nth(5).unwrap().parse::<i64>().unwrap();
-            max_score(players, last_marble)
-        })
-        .sum::<i64>();
-}
+mod circle;
 
-fn part2(contents: String) -> i64 {
-    return contents
-        .lines()
-        .map(|line| {
-            let mut parts = line.split_whitespace();
-            let players = parts.next().unwrap().parse::<i64>().unwrap();
-            let last_marble = parts.nth(5).unwrap().parse::<i64>().unwrap();
-            max_score(players, last_marble * 100)
-        })
-        .sum::<i64>();
-}
+use circle::{GameError, MarbleRules, Turn};
 
-fn max_score(players: i64, last_marble: i64) -> i64 {
-    let mut scores = vec![0; players as usize];
-    let mut circle = VecDeque::new();
-    circle.push_back(0);
+fn part1(contents: String) -> Result<Vec<GameResult>, GameError> {
+    play_all(&contents, 1)
+}
 
+fn part2(contents: String, multiplier: usize) -> Result<Vec<GameResult>, GameError> {
+    play_all(&contents, multiplier)
+}
 
-    let mut max_score = 0;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct GameResult {
+    players: usize,
+    last_marble: usize,
+    winner: usize,
+    high_score: i64,
+}
 
-    for marble in 1..=last_marble {
-        if marble % 23 == 0 {
-            for _ in 0..7 {
-                let temp = circle.pop_back().unwrap();
-                circle.push_front(temp);
-            }
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} players, last marble {}: player {} wins with {}",
+            self.players, self.last_marble, self.winner, self.high_score
+        )
+    }
+}
 
-            scores[(marble % players) as usize] += marble + circle.pop_front().unwrap();
-            max_score = std::cmp::max(max_score, scores[(marble % players) as usize]);
-        } else {
-            for _ in 0..2 {
-                let temp = circle.pop_front().unwrap();
-                circle.push_back(temp);
-            }
+// Each game line played on its own, with its last marble scaled up
+fn play_all(contents: &str, multiplier: usize) -> Result<Vec<GameResult>, GameError> {
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let (players, last_marble) = parse_game(line);
+            // Too many either way, so saturating keeps it from wrapping round
+            let last_marble = last_marble.saturating_mul(multiplier);
+            let (winner, high_score) = circle::play(players, last_marble, MarbleRules::default())?.winner();
+            Ok(GameResult {
+                players,
+                last_marble,
+                winner,
+                high_score,
+            })
+        })
+        .collect()
+}
 
-            circle.push_front(marble);
-        }
-    }
+fn results_string(results: &[GameResult]) -> String {
+    results.iter().map(|r| r.to_string()).collect::<Vec<String>>().join("\n")
+}
 
-    return max_score;
+// "10 players; last marble is worth 1618 points"
+fn parse_game(line: &str) -> (usize, usize) {
+    let mut parts = line.split_whitespace();
+    let players = parts.next().unwrap().parse::<usize>().unwrap();
+    let last_marble = parts.nth(5).unwrap().parse::<usize>().unwrap();
+    (players, last_marble)
 }
 
 #[cfg(test)]
@@ -67,17 +75,107 @@ mod tests {
         let contents =
             fs::read_to_string("example.txt").expect("Should have been able to read the file");
 
-        assert_eq!(part1(contents), 249477);
+        assert_eq!(part1(contents).unwrap().iter().map(|r| r.high_score).sum::<i64>(), 249477);
+    }
+
+    #[test]
+    fn circle_test() {
+        let game = circle::play(9, 25, MarbleRules::default()).unwrap();
+
+        assert_eq!(game.winner(), (5, 32));
+        assert_eq!(game.scores.iter().sum::<i64>(), 32);
+        let turn = Turn {
+            player: game.player(25),
+            marbles: game.circle.marbles(),
+            current: game.circle.current(),
+        };
+        assert_eq!(turn.to_string(), "[7]  0 16  8 17  4 18 19  2 24 20(25)10 21  5 22 11  1 12  6 13  3 14  7 15 ");
+        assert_eq!(game.circle.len(), 24);
+        assert_eq!(game.circle.window(2), vec![24, 20, 25, 10, 21]);
+        assert_eq!(game.circle.window(100).len(), 23);
+
+        for (players, last_marble, high_score) in
+            [(10, 1618, 8317), (13, 7999, 146373), (17, 1104, 2764), (21, 6111, 54718), (30, 5807, 37305)]
+        {
+            assert_eq!(circle::play(players, last_marble, MarbleRules::default()).unwrap().winner().1, high_score);
+        }
+
+        assert_eq!(circle::play(0, 25, MarbleRules::default()).err(), Some(GameError::NoPlayers));
+        assert_eq!(circle::replay(0, 25, MarbleRules::default()).err(), Some(GameError::NoPlayers));
+        let too_many = u32::MAX as usize + 1;
+        assert_eq!(
+            circle::play(9, too_many, MarbleRules::default()).err(),
+            Some(GameError::TooManyMarbles { last_marble: too_many })
+        );
+        assert_eq!(
+            part1("0 players; last marble is worth 25 points".to_string()),
+            Err(GameError::NoPlayers)
+        );
+        assert_eq!(
+            part2("9 players; last marble is worth 50000000 points".to_string(), 100),
+            Err(GameError::TooManyMarbles { last_marble: 5_000_000_000 })
+        );
+    }
+
+    #[test]
+    fn batch_test() {
+        let contents = "9 players; last marble is worth 25 points\n\n10 players; last marble is worth 1618 points\n";
+        let results = part1(contents.to_string()).unwrap();
+
+        assert_eq!(
+            results,
+            vec![
+                GameResult { players: 9, last_marble: 25, winner: 5, high_score: 32 },
+                GameResult { players: 10, last_marble: 1618, winner: 10, high_score: 8317 },
+            ]
+        );
+        assert_eq!(
+            results_string(&results),
+            "9 players, last marble 25: player 5 wins with 32\n10 players, last marble 1618: player 10 wins with 8317"
+        );
+        assert_eq!(part2(contents.to_string(), 1), Ok(results));
+        assert_eq!(part2(contents.to_string(), 2).unwrap()[0].last_marble, 50);
+    }
+
+    #[test]
+    fn replay_test() {
+        let turns: Vec<String> = circle::replay(9, 25, MarbleRules::default()).unwrap().map(|t| t.to_string()).collect();
+
+        assert_eq!(turns.len(), 26);
+        assert_eq!(turns[0], "[-] (0)");
+        assert_eq!(turns[1], "[1]  0 (1)");
+        assert_eq!(turns[4], "[4]  0 (4) 2  1  3 ");
+        assert_eq!(turns[10], "[1]  0  8  4  9  2(10) 5  1  6  3  7 ");
+        assert_eq!(turns[22], "[4]  0 16  8 17  4 18  9 19  2 20 10 21  5(22)11  1 12  6 13  3 14  7 15 ");
+        assert_eq!(turns[23], "[5]  0 16  8 17  4 18(19) 2 20 10 21  5 22 11  1 12  6 13  3 14  7 15 ");
+        assert_eq!(turns[25], "[7]  0 16  8 17  4 18 19  2 24 20(25)10 21  5 22 11  1 12  6 13  3 14  7 15 ");
+
+        let rules = MarbleRules::parse("5,2,1").unwrap();
+        let game = circle::play(2, 10, rules).unwrap();
+        assert_eq!(game.circle.marbles(), vec![0, 1, 3, 6, 8, 9, 4]);
+        assert_eq!(game.scores, vec![5 + 2, 10 + 7]);
+        assert_eq!(MarbleRules::parse("23,7"), None);
+        assert_eq!(MarbleRules::parse("0,7,2"), None);
+
+        // Marble 2 takes marble 0 out of the circle
+        let rules = MarbleRules::parse("2,1,1").unwrap();
+        let game = circle::play(2, 5, rules).unwrap();
+        assert_eq!(game.circle.marbles(), vec![5, 3]);
+        assert_eq!(game.scores, vec![0, 2 + 4 + 1]);
+        let turns: Vec<Vec<usize>> = circle::replay(2, 5, rules).unwrap().map(|t| t.marbles).collect();
+        assert_eq!(turns, vec![vec![0], vec![0, 1], vec![1], vec![3, 1], vec![3], vec![5, 3]]);
     }
 }
 fn main() {
     let args: Vec<String> = env::args().collect();
+    let (flags, paths): (Vec<String>, Vec<String>) =
+        args.iter().skip(1).cloned().partition(|arg| arg.starts_with("--"));
     let year = "2018".to_string();
     let day = "9".to_string();
 
     let root = env::current_dir().unwrap();
-    let path_str = if args.len() > 1 {
-        args[1].clone()
+    let path_str = if !paths.is_empty() {
+        paths[0].clone()
     } else if root.ends_with(format!("rust_{}_{}", year, day)) {
         format!("../../../Inputs/{}_{}.txt", year, day)
     } else {
@@ -85,20 +183,92 @@ fn main() {
     };
 
 
-    let contents = fs::read_to_string(if args.len() > 1 {path_str} else {RelativePath::new(&path_str).to_path(&root).display().to_string()})
+    let contents = fs::read_to_string(if !paths.is_empty() {path_str} else {RelativePath::new(&path_str).to_path(&root).display().to_string()})
         .expect("Should have been able to read the file");
 
+    let multiplier = flags
+        .iter()
+        .find_map(|flag| flag.strip_prefix("--multiplier="))
+        .map_or(100, |n| n.parse::<usize>().expect("Should have been a number"));
+
     let part1_timer = Instant::now();
+    let results = match part1(contents.clone()) {
+        Ok(results) => results,
+        Err(error) => {
+            println!("\nInvalid game:\n{}", error);
+            return;
+        }
+    };
     println!(
-        "\nPart 1:\nHighest score: {}\nRan in {:.5?}",
-        part1(contents.clone()),
+        "\nPart 1:\n{}\nRan in {:.5?}",
+        results_string(&results),
         part1_timer.elapsed()
     );
 
     let part2_timer = Instant::now();
+    let results = match part2(contents.clone(), multiplier) {
+        Ok(results) => results,
+        Err(error) => {
+            println!("\nInvalid game:\n{}", error);
+            return;
+        }
+    };
     println!(
-        "\nPart 2:\nHighest score: {}\nRan in {:.5?}",
-        part2(contents.clone()),
+        "\nPart 2:\n{}\nRan in {:.5?}",
+        results_string(&results),
         part2_timer.elapsed()
     );
-}
\ No newline at end of file
+
+    let rules = flags
+        .iter()
+        .find_map(|flag| flag.strip_prefix("--rules="))
+        .map_or(MarbleRules::default(), |spec| MarbleRules::parse(spec).expect("Should have been SCORING,REMOVE_BACK,PLACE_FORWARD"));
+
+    for flag in &flags {
+        match flag.split_once('=') {
+            Some(("--game", game)) => {
+                let (players, last_marble) = game.split_once(',').expect("Should have been PLAYERS,LAST_MARBLE");
+                let last_marble: usize = last_marble.parse().expect("Should have been a number");
+                let game = match circle::play(players.parse().expect("Should have been a number"), last_marble, rules) {
+                    Ok(game) => game,
+                    Err(error) => {
+                        println!("\nInvalid game:\n{}", error);
+                        continue;
+                    }
+                };
+                let (winner, score) = game.winner();
+
+                println!("\nPlayer {} wins with {} points", winner, score);
+                for (player, score) in game.scores.iter().enumerate() {
+                    println!("Player {:<4}{}", player + 1, score);
+                }
+                let turn = Turn {
+                    player: game.player(last_marble),
+                    marbles: if game.circle.len() <= 21 { game.circle.marbles() } else { game.circle.window(10) },
+                    current: game.circle.current(),
+                };
+                println!("Circle: {}", turn);
+            }
+            Some(("--replay", game)) => {
+                let (players, last_marble) = game.split_once(',').expect("Should have been PLAYERS,LAST_MARBLE");
+                let replay = match circle::replay(
+                    players.parse().expect("Should have been a number"),
+                    last_marble.parse().expect("Should have been a number"),
+                    rules,
+                ) {
+                    Ok(replay) => replay,
+                    Err(error) => {
+                        println!("\nInvalid game:\n{}", error);
+                        continue;
+                    }
+                };
+                println!();
+                for turn in replay {
+                    println!("{}", turn);
+                }
+            }
+            Some(("--rules", _)) | Some(("--multiplier", _)) => {}
+            _ => panic!("Unknown option {}", flag),
+        }
+    }
+}